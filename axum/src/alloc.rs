@@ -0,0 +1,149 @@
+//! The global allocator, selected by cargo features: `jemalloc` and `system-alloc`
+//! take precedence over `mimalloc` (default), so that they can be selected
+//! without `--no-default-features`. The system allocator is used if none.
+//!
+//! With `alloc-count`, it's wrapped to count the allocations made while
+//! serving each request (see `counting::CountAllocsLayer`).
+
+#[cfg(all(feature = "jemalloc", feature = "system-alloc"))]
+compile_error!("select at most one allocator of `jemalloc` and `system-alloc`");
+
+#[cfg(all(feature = "mimalloc", not(any(feature = "jemalloc", feature = "system-alloc"))))]
+use mimalloc::MiMalloc as Allocator;
+#[cfg(all(feature = "mimalloc", not(any(feature = "jemalloc", feature = "system-alloc"))))]
+const ALLOCATOR: Allocator = mimalloc::MiMalloc;
+
+#[cfg(feature = "jemalloc")]
+use tikv_jemallocator::Jemalloc as Allocator;
+#[cfg(feature = "jemalloc")]
+const ALLOCATOR: Allocator = tikv_jemallocator::Jemalloc;
+
+#[cfg(any(feature = "system-alloc", not(any(feature = "mimalloc", feature = "jemalloc"))))]
+use std::alloc::System as Allocator;
+#[cfg(any(feature = "system-alloc", not(any(feature = "mimalloc", feature = "jemalloc"))))]
+const ALLOCATOR: Allocator = std::alloc::System;
+
+#[cfg(not(feature = "alloc-count"))]
+#[global_allocator]
+static GLOBAL: Allocator = ALLOCATOR;
+
+#[cfg(feature = "alloc-count")]
+#[global_allocator]
+static GLOBAL: counting::Counting<Allocator> = counting::Counting(ALLOCATOR);
+
+#[cfg(feature = "alloc-count")]
+pub mod counting {
+    use std::{
+        alloc::{GlobalAlloc, Layout},
+        cell::Cell,
+        future::Future,
+        task::{Context, Poll},
+    };
+
+    use axum::{extract::Request, http::HeaderValue, response::Response};
+    use futures::future::BoxFuture;
+    use tower::{Layer, Service};
+
+    tokio::task_local! {
+        /// Allocations of the task currently being polled.
+        static ALLOCS: Allocs;
+    }
+
+    #[derive(Default)]
+    struct Allocs {
+        count: Cell<u64>,
+        bytes: Cell<u64>,
+    }
+
+    pub struct Counting<A>(pub A);
+
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for Counting<A> {
+        #[inline]
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            self.0.alloc(layout)
+        }
+
+        #[inline]
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            self.0.alloc_zeroed(layout)
+        }
+
+        #[inline]
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record(new_size);
+            self.0.realloc(ptr, layout, new_size)
+        }
+
+        #[inline]
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.0.dealloc(ptr, layout)
+        }
+    }
+
+    /// Never allocates by itself: `ALLOCS` is const-initialized and has no destructor.
+    #[inline]
+    fn record(size: usize) {
+        let _ = ALLOCS.try_with(|allocs| {
+            allocs.count.set(allocs.count.get() + 1);
+            allocs.bytes.set(allocs.bytes.get() + size as u64);
+        });
+    }
+
+    /// Run `task` counting its allocations, and return its output
+    /// with `(number of allocations, allocated bytes)`.
+    pub async fn count<T>(task: impl Future<Output = T>) -> (T, (u64, u64)) {
+        ALLOCS
+            .scope(Allocs::default(), async {
+                let output = task.await;
+                let counts = ALLOCS.with(|allocs| (allocs.count.get(), allocs.bytes.get()));
+                (output, counts)
+            })
+            .await
+    }
+
+    /// Report the allocations made while serving each request
+    /// as `X-Alloc-Count` and `X-Alloc-Bytes` response headers.
+    #[derive(Clone)]
+    pub struct CountAllocsLayer;
+
+    impl<S> Layer<S> for CountAllocsLayer {
+        type Service = CountAllocs<S>;
+
+        fn layer(&self, inner: S) -> Self::Service {
+            CountAllocs { inner }
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct CountAllocs<S> {
+        inner: S,
+    }
+
+    impl<S> Service<Request> for CountAllocs<S>
+    where
+        S: Service<Request, Response = Response> + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        type Response = Response;
+        type Error = S::Error;
+        type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, request: Request) -> Self::Future {
+            let response = self.inner.call(request);
+            Box::pin(async move {
+                let (response, (count, bytes)) = count(response).await;
+                let mut response = response?;
+                let headers = response.headers_mut();
+                headers.insert("x-alloc-count", HeaderValue::from(count));
+                headers.insert("x-alloc-bytes", HeaderValue::from(bytes));
+                Ok(response)
+            })
+        }
+    }
+}
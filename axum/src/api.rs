@@ -0,0 +1,168 @@
+//! `GET`/`POST /api/worlds` and `GET`/`PUT`/`PATCH`/`DELETE /api/worlds/:id`,
+//! a CRUD service on the `ApiWorld` table.
+//!
+//! Invalid input is answered by 422 with the reason as text, unknown ids by
+//! 404, and taken ids by 409. Bodies are (de)serialized by `axum::Json`
+//! (serde_json) whichever serializer is selected.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+use crate::pg::models::{WorldInput, WorldsPage, WorldsPageParams};
+use crate::{DatabaseConnection, PgConnection};
+
+pub fn router() -> Router<Arc<PgConnection>> {
+    Router::new()
+        .route("/api/worlds", get(list).post(create))
+        .route(
+            "/api/worlds/:id",
+            get(read).put(replace).patch(modify).delete(delete),
+        )
+}
+
+fn not_found(id: impl std::fmt::Display) -> Response {
+    (StatusCode::NOT_FOUND, format!("world {id} not found")).into_response()
+}
+
+fn unprocessable(message: String) -> Response {
+    (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+}
+
+async fn list(
+    DatabaseConnection(conn): DatabaseConnection,
+    Query(params): Query<WorldsPageParams>,
+) -> Response {
+    let (after, limit, offset) = match params.parse() {
+        Ok(page) => page,
+        Err(message) => return unprocessable(message),
+    };
+
+    let worlds = conn
+        .list_api_worlds(after, limit, offset)
+        .await
+        .expect("error listing worlds");
+    let next_after = match worlds.last() {
+        Some(last) if worlds.len() as i64 == limit => Some(last.id),
+        _ => None,
+    };
+
+    Json(WorldsPage { worlds, next_after }).into_response()
+}
+
+async fn create(
+    DatabaseConnection(conn): DatabaseConnection,
+    Json(input): Json<WorldInput>,
+) -> Response {
+    let (id, randomnumber) = match input.validate_new() {
+        Ok(new) => new,
+        Err(message) => return unprocessable(message),
+    };
+
+    let inserted = conn
+        .insert_api_world(id, randomnumber)
+        .await
+        .expect("error inserting world");
+    match inserted {
+        Some(world) => (
+            StatusCode::CREATED,
+            [(header::LOCATION, format!("/api/worlds/{}", world.id))],
+            Json(world),
+        )
+            .into_response(),
+        None => {
+            let message = match id {
+                Some(id) => format!("world {id} already exists"),
+                None => String::from("the generated id was already taken, retry"),
+            };
+            (StatusCode::CONFLICT, message).into_response()
+        }
+    }
+}
+
+async fn read(DatabaseConnection(conn): DatabaseConnection, Path(id): Path<u32>) -> Response {
+    // Ids beyond `i32` can't exist.
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id);
+    };
+
+    match conn
+        .select_api_world(id)
+        .await
+        .expect("error loading world")
+    {
+        Some(world) => Json(world).into_response(),
+        None => not_found(id),
+    }
+}
+
+async fn replace(
+    DatabaseConnection(conn): DatabaseConnection,
+    Path(id): Path<u32>,
+    Json(input): Json<WorldInput>,
+) -> Response {
+    // Ids beyond `i32` can't exist.
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id);
+    };
+    let randomnumber = match input.validate_put(id) {
+        Ok(randomnumber) => randomnumber,
+        Err(message) => return unprocessable(message),
+    };
+
+    match conn
+        .update_api_world(id, Some(randomnumber))
+        .await
+        .expect("error updating world")
+    {
+        Some(world) => Json(world).into_response(),
+        None => not_found(id),
+    }
+}
+
+async fn modify(
+    DatabaseConnection(conn): DatabaseConnection,
+    Path(id): Path<u32>,
+    Json(input): Json<WorldInput>,
+) -> Response {
+    // Ids beyond `i32` can't exist.
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id);
+    };
+    let randomnumber = match input.validate_patch(id) {
+        Ok(randomnumber) => randomnumber,
+        Err(message) => return unprocessable(message),
+    };
+
+    match conn
+        .update_api_world(id, randomnumber)
+        .await
+        .expect("error updating world")
+    {
+        Some(world) => Json(world).into_response(),
+        None => not_found(id),
+    }
+}
+
+async fn delete(DatabaseConnection(conn): DatabaseConnection, Path(id): Path<u32>) -> Response {
+    // Ids beyond `i32` can't exist.
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id);
+    };
+
+    if conn
+        .delete_api_world(id)
+        .await
+        .expect("error deleting world")
+    {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        not_found(id)
+    }
+}
@@ -0,0 +1,270 @@
+//! Access log written by a batching task per runtime, so that requests
+//! never wait for the writes. Configured by environment variables:
+//!
+//! - `ACCESS_LOG_FORMAT`: `common` (default), `combined` or `json`
+//! - `ACCESS_LOG_PATH`: file to append to (stdout by default)
+//! - `ACCESS_LOG_SAMPLE`: log 1 request out of N (1 by default)
+
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    num::NonZeroU64,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{ConnectInfo, Request},
+    http::{header, Version},
+    response::Response,
+};
+use futures::future::BoxFuture;
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+use tower::{Layer, Service};
+
+use super::get_env_or;
+
+const CHANNEL_CAPACITY: usize = 1 << 16;
+const BATCH_SIZE: usize = 1 << 10;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Common,
+    Combined,
+    Json,
+}
+
+#[derive(Clone)]
+pub struct AccessLogLayer {
+    format: Format,
+    sample: NonZeroU64,
+    counter: Arc<AtomicU64>,
+    lines: mpsc::Sender<String>,
+}
+
+impl AccessLogLayer {
+    /// Spawn the writer task onto the current runtime.
+    pub fn new() -> Self {
+        let format = match std::env::var("ACCESS_LOG_FORMAT").as_deref() {
+            Err(_) | Ok("common") => Format::Common,
+            Ok("combined") => Format::Combined,
+            Ok("json") => Format::Json,
+            Ok(other) => panic!("unknown ACCESS_LOG_FORMAT: `{other}`"),
+        };
+        let path = std::env::var("ACCESS_LOG_PATH").ok();
+
+        let (lines, mut rx) = mpsc::channel::<String>(CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            let mut out: Pin<Box<dyn tokio::io::AsyncWrite + Send>> = match path {
+                None => Box::pin(tokio::io::stdout()),
+                Some(path) => Box::pin(
+                    tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await
+                        .expect("could not open ACCESS_LOG_PATH"),
+                ),
+            };
+
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let mut buf = Vec::new();
+            while rx.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+                for line in batch.drain(..) {
+                    buf.extend_from_slice(line.as_bytes());
+                }
+                let written = async {
+                    out.write_all(&buf).await?;
+                    out.flush().await
+                };
+                if let Err(error) = written.await {
+                    eprintln!("access log write error: {error}");
+                }
+                buf.clear();
+            }
+        });
+
+        let sample = NonZeroU64::new(get_env_or("ACCESS_LOG_SAMPLE", 1))
+            .expect("ACCESS_LOG_SAMPLE must be at least 1");
+
+        Self {
+            format,
+            sample,
+            counter: Arc::new(AtomicU64::new(0)),
+            lines,
+        }
+    }
+}
+
+impl<S> Layer<S> for AccessLogLayer {
+    type Service = AccessLog<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLog {
+            log: self.clone(),
+            inner,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AccessLog<S> {
+    log: AccessLogLayer,
+    inner: S,
+}
+
+impl<S> Service<Request> for AccessLog<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if self.log.counter.fetch_add(1, Ordering::Relaxed) % self.log.sample != 0 {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let entry = Entry::new(&request);
+        let start = Instant::now();
+        let response = self.inner.call(request);
+
+        let log = self.log.clone();
+        Box::pin(async move {
+            let response = response.await?;
+            let line = entry.format(log.format, &response, start.elapsed().as_micros());
+            // Drop the line rather than block the request when the writer is behind.
+            let _ = log.lines.try_send(line);
+            Ok(response)
+        })
+    }
+}
+
+/// What is needed from the request, taken before it is consumed.
+struct Entry {
+    remote: Option<SocketAddr>,
+    method: String,
+    path: String,
+    version: Version,
+    referer: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl Entry {
+    fn new(request: &Request) -> Self {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            remote: request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| *addr),
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+            version: request.version(),
+            referer: header(header::REFERER),
+            user_agent: header(header::USER_AGENT),
+        }
+    }
+
+    fn format(self, format: Format, response: &Response<Body>, latency_us: u128) -> String {
+        let Self {
+            remote,
+            method,
+            path,
+            version,
+            referer,
+            user_agent,
+        } = self;
+        let now = SystemTime::now();
+        let ip = remote.map_or_else(|| "-".to_string(), |addr| addr.ip().to_string());
+        let status = response.status().as_u16();
+        let bytes = response.body().size_hint().exact().unwrap_or(0);
+
+        match format {
+            Format::Common => format!(
+                "{ip} - - [{}] \"{method} {path} {version:?}\" {status} {bytes}\n",
+                clf_time(now),
+            ),
+            Format::Combined => format!(
+                "{ip} - - [{}] \"{method} {path} {version:?}\" {status} {bytes} \"{}\" \"{}\"\n",
+                clf_time(now),
+                referer.as_deref().unwrap_or("-"),
+                user_agent.as_deref().unwrap_or("-"),
+            ),
+            Format::Json => format!(
+                "{{\"time\":{},\"ip\":\"{ip}\",\"method\":\"{method}\",\"path\":{},\"status\":{status},\"bytes\":{bytes},\"latency_us\":{latency_us},\"user_agent\":{}}}\n",
+                now.duration_since(UNIX_EPOCH).unwrap().as_millis(),
+                json_string(&path),
+                json_string(user_agent.as_deref().unwrap_or("")),
+            ),
+        }
+    }
+}
+
+/// Quote and escape `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Format a time as `10/Oct/2000:13:55:36 +0000`.
+fn clf_time(time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let secs = time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{day:02}/{}/{year}:{:02}:{:02}:{:02} +0000",
+        MONTHS[month as usize - 1],
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
@@ -0,0 +1,158 @@
+use axum::async_trait;
+use axum::extract::{rejection::JsonRejection, FromRequest, Request};
+use axum::http::{header, HeaderValue};
+use axum_core::response::{IntoResponse, Response};
+use bytes::BytesMut;
+use serde::de::DeserializeOwned;
+
+use crate::common::models::Message;
+use crate::pg::models::{Fortune, World};
+
+/// A JSON response written by hand with `itoa`, without going through serde.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+axum_core::__impl_deref!(Json);
+
+impl<T> From<T> for Json<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+/// Request bodies are still parsed by serde_json, as axum's `Json` does.
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = JsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(req, state).await?;
+        Ok(Json(value))
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: WriteJson,
+{
+    fn into_response(self) -> Response {
+        let mut buf = BytesMut::with_capacity(self.0.size_hint());
+        self.0.write_json(&mut buf);
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+            )],
+            buf.freeze(),
+        )
+            .into_response()
+    }
+}
+
+/// Types that know how to write themselves as JSON.
+pub trait WriteJson {
+    /// Upper estimate of the written length, used as the buffer capacity.
+    fn size_hint(&self) -> usize;
+    fn write_json(&self, buf: &mut BytesMut);
+}
+
+impl WriteJson for Message {
+    #[inline]
+    fn size_hint(&self) -> usize {
+        br#"{"message":""}"#.len() + self.message.len()
+    }
+
+    #[inline]
+    fn write_json(&self, buf: &mut BytesMut) {
+        buf.extend_from_slice(br#"{"message":"#);
+        write_str(self.message, buf);
+        buf.extend_from_slice(b"}");
+    }
+}
+
+impl WriteJson for Fortune<'_> {
+    #[inline]
+    fn size_hint(&self) -> usize {
+        br#"{"id":10000,"message":""}"#.len() + self.message.len()
+    }
+
+    #[inline]
+    fn write_json(&self, buf: &mut BytesMut) {
+        let mut itoa = itoa::Buffer::new();
+        buf.extend_from_slice(br#"{"id":"#);
+        buf.extend_from_slice(itoa.format(self.id).as_bytes());
+        buf.extend_from_slice(br#","message":"#);
+        write_str(self.message, buf);
+        buf.extend_from_slice(b"}");
+    }
+}
+
+impl WriteJson for World {
+    #[inline]
+    fn size_hint(&self) -> usize {
+        br#"{"id":10000,"randomNumber":10000}"#.len()
+    }
+
+    #[inline]
+    fn write_json(&self, buf: &mut BytesMut) {
+        let mut itoa = itoa::Buffer::new();
+        buf.extend_from_slice(br#"{"id":"#);
+        buf.extend_from_slice(itoa.format(self.id).as_bytes());
+        buf.extend_from_slice(br#","randomNumber":"#);
+        buf.extend_from_slice(itoa.format(self.randomnumber).as_bytes());
+        buf.extend_from_slice(b"}");
+    }
+}
+
+impl<T: WriteJson> WriteJson for Vec<T> {
+    #[inline]
+    fn size_hint(&self) -> usize {
+        2 + self.iter().map(|t| t.size_hint() + 1).sum::<usize>()
+    }
+
+    #[inline]
+    fn write_json(&self, buf: &mut BytesMut) {
+        buf.extend_from_slice(b"[");
+        for (i, t) in self.iter().enumerate() {
+            if i != 0 {
+                buf.extend_from_slice(b",");
+            }
+            t.write_json(buf);
+        }
+        buf.extend_from_slice(b"]");
+    }
+}
+
+/// Write `s` as a quoted JSON string.
+fn write_str(s: &str, buf: &mut BytesMut) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    buf.extend_from_slice(b"\"");
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let escaped: &[u8] = match b {
+            b'"' => br#"\""#,
+            b'\\' => br"\\",
+            b'\n' => br"\n",
+            b'\r' => br"\r",
+            b'\t' => br"\t",
+            0x00..=0x1f => {
+                buf.extend_from_slice(&s.as_bytes()[start..i]);
+                buf.extend_from_slice(br"\u00");
+                buf.extend_from_slice(&[HEX[(b >> 4) as usize], HEX[(b & 0xf) as usize]]);
+                start = i + 1;
+                continue;
+            }
+            _ => continue,
+        };
+        buf.extend_from_slice(&s.as_bytes()[start..i]);
+        buf.extend_from_slice(escaped);
+        start = i + 1;
+    }
+    buf.extend_from_slice(&s.as_bytes()[start..]);
+    buf.extend_from_slice(b"\"");
+}
@@ -0,0 +1,120 @@
+//! Per-runtime load limits of `serve_hyper`, both unlimited by default:
+//!
+//! * `MAX_CLIENT_CONNECTIONS`: concurrent connections. Past it, accepts wait
+//!   for a connection to close, leaving new ones in the listen backlog.
+//! * `MAX_IN_FLIGHT`: requests being handled. Past it, requests are answered
+//!   `503 Service Unavailable` with `Retry-After: RETRY_AFTER_SECS` (1 by default).
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, Service};
+
+use super::get_env_or;
+
+/// Caps the connections of a runtime, if `MAX_CLIENT_CONNECTIONS` is set.
+#[derive(Clone)]
+pub struct ConnectionLimit(Option<Arc<Semaphore>>);
+
+impl ConnectionLimit {
+    pub fn from_env() -> Self {
+        let max: usize = get_env_or("MAX_CLIENT_CONNECTIONS", 0);
+        Self((max > 0).then(|| Arc::new(Semaphore::new(max))))
+    }
+
+    /// Wait for room for one more connection, kept until the permit is dropped.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.0 {
+            Some(semaphore) => Some(semaphore.clone().acquire_owned().await.unwrap()),
+            None => None,
+        }
+    }
+}
+
+/// Answers `503` past `MAX_IN_FLIGHT` requests being handled by a runtime.
+#[derive(Clone)]
+pub struct InFlightLimitLayer {
+    in_flight: Arc<AtomicUsize>,
+    max: usize,
+    retry_after: HeaderValue,
+}
+
+impl InFlightLimitLayer {
+    /// `None` if `MAX_IN_FLIGHT` is not set.
+    pub fn from_env() -> Option<Self> {
+        let max: usize = get_env_or("MAX_IN_FLIGHT", 0);
+        (max > 0).then(|| Self {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max,
+            retry_after: HeaderValue::from(get_env_or::<u32>("RETRY_AFTER_SECS", 1)),
+        })
+    }
+}
+
+impl<S> Layer<S> for InFlightLimitLayer {
+    type Service = InFlightLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InFlightLimit {
+            inner,
+            limit: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InFlightLimit<S> {
+    inner: S,
+    limit: InFlightLimitLayer,
+}
+
+/// Counts a request as in flight until dropped.
+struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl<S> Service<Request> for InFlightLimit<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let in_flight = InFlight(self.limit.in_flight.clone());
+        if in_flight.0.fetch_add(1, Ordering::Relaxed) >= self.limit.max {
+            let response = (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(header::RETRY_AFTER, self.limit.retry_after.clone())],
+            )
+                .into_response();
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let _in_flight = in_flight;
+            response.await
+        })
+    }
+}
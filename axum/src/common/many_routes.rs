@@ -0,0 +1,37 @@
+//! `ROUTES` (1000 by default) static and parameterized routes with shared
+//! prefixes under `/r`, to measure how the router scales with its size.
+//!
+//! Route `i` is the `i % 3`th of these shapes, 8 of each per group
+//! `g = i / 24` and leaf `l = i % 24 / 3`:
+//!
+//! * `/r/g{g}/s{l}`
+//! * `/r/g{g}/s{l}/:id`
+//! * `/r/g{g}/s{l}/:id/items`
+//!
+//! Unlike ohkami's, which are generated at build time by its `build.rs`,
+//! these are added at startup, so only the startup time depends on `ROUTES`.
+
+use axum::{extract::Path, routing::get, Router};
+
+use super::get_env_or;
+
+async fn static_route() -> &'static str {
+    "static"
+}
+
+async fn param_route(Path(_id): Path<String>) -> &'static str {
+    "param"
+}
+
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    let routes: usize = get_env_or("ROUTES", 1000);
+
+    (0..routes).fold(Router::new(), |router, i| {
+        let (g, l) = (i / 24, i % 24 / 3);
+        match i % 3 {
+            0 => router.route(&format!("/r/g{g}/s{l}"), get(static_route)),
+            1 => router.route(&format!("/r/g{g}/s{l}/:id"), get(param_route)),
+            _ => router.route(&format!("/r/g{g}/s{l}/:id/items"), get(param_route)),
+        }
+    })
+}
@@ -0,0 +1,87 @@
+//! Wrap the app in `MIDDLEWARES` (8 by default) pairs of a no-op layer and
+//! a layer overwriting `x-middleware`, to measure the cost of composing layers.
+//!
+//! The pairs wrap the finished app once, outside the layers of `serve_hyper`,
+//! rather than each of its routes by `Router::layer`, which would box every
+//! layer of every route. The stack is composed with static types and only
+//! boxed once at the top, so that the depth doesn't add dynamic calls: that's
+//! why it's one of 0, 1, 4, 8, 16 and 64.
+
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use axum::{
+    http::{HeaderName, HeaderValue, Request},
+    response::Response,
+    Router,
+};
+use hyper::body::Incoming;
+use tower::{util::BoxCloneService, Layer, Service};
+use tower_http::set_header::{SetResponseHeader, SetResponseHeaderLayer};
+
+use super::get_env_or;
+
+/// Forward requests to the inner service as they are.
+#[derive(Clone)]
+struct NoOpLayer;
+
+impl<S> Layer<S> for NoOpLayer {
+    type Service = NoOp<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NoOp { inner }
+    }
+}
+
+#[derive(Clone)]
+struct NoOp<S> {
+    inner: S,
+}
+
+impl<S: Service<Request<Incoming>>> Service<Request<Incoming>> for NoOp<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Incoming>) -> Self::Future {
+        self.inner.call(request)
+    }
+}
+
+type Pair<S> = NoOp<SetResponseHeader<S, HeaderValue>>;
+type Four<S> = Pair<Pair<Pair<Pair<S>>>>;
+type Sixteen<S> = Four<Four<Four<Four<S>>>>;
+
+fn pair<S>(inner: S) -> Pair<S> {
+    let set_header = SetResponseHeaderLayer::overriding(
+        HeaderName::from_static("x-middleware"),
+        HeaderValue::from_static("axum"),
+    );
+    NoOpLayer.layer(set_header.layer(inner))
+}
+
+fn four<S>(inner: S) -> Four<S> {
+    pair(pair(pair(pair(inner))))
+}
+
+fn sixteen<S>(inner: S) -> Sixteen<S> {
+    four(four(four(four(inner))))
+}
+
+pub fn stack(app: Router<()>) -> BoxCloneService<Request<Incoming>, Response, Infallible> {
+    match get_env_or("MIDDLEWARES", 8) {
+        0 => BoxCloneService::new(app),
+        1 => BoxCloneService::new(pair(app)),
+        4 => BoxCloneService::new(four(app)),
+        8 => BoxCloneService::new(four(four(app))),
+        16 => BoxCloneService::new(sixteen(app)),
+        64 => BoxCloneService::new(sixteen(sixteen(sixteen(sixteen(app))))),
+        n => panic!("unsupported MIDDLEWARES {n}: the stack has 0, 1, 4, 8, 16 or 64 pairs"),
+    }
+}
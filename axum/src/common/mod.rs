@@ -1,16 +1,46 @@
 use std::{env, str::FromStr};
 
+use axum::routing::MethodRouter;
 use core::fmt::Debug;
 use rand::{distributions::Uniform, rngs::SmallRng, Rng};
+pub mod limits;
 pub mod models;
+pub mod timeouts;
 pub mod utils;
 
+#[cfg(feature = "access-log")]
+pub mod access_log;
+#[cfg(feature = "many-routes")]
+pub mod many_routes;
+#[cfg(feature = "middleware-stack")]
+pub mod middleware_stack;
+#[cfg(feature = "pprof")]
+pub mod profiling;
+#[cfg(feature = "query-stats")]
+pub mod query_stats;
 #[cfg(feature = "simd-json")]
 pub mod simd_json;
+#[cfg(feature = "sonic-rs")]
+pub mod sonic_rs;
+#[cfg(feature = "static-files")]
+pub mod static_files;
+#[cfg(feature = "itoa-json")]
+pub mod itoa_json;
+#[cfg(feature = "ws")]
+pub mod ws;
+
+#[cfg(any(
+    all(feature = "simd-json", feature = "sonic-rs"),
+    all(feature = "simd-json", feature = "itoa-json"),
+    all(feature = "sonic-rs", feature = "itoa-json"),
+))]
+compile_error!("select at most one JSON serializer of `simd-json`, `sonic-rs` and `itoa-json`");
 
 #[allow(dead_code)]
 pub const SELECT_ALL_FORTUNES: &str = "SELECT * FROM fortune";
 #[allow(dead_code)]
+pub const SELECT_FORTUNE_BY_ID: &str = "SELECT id, message FROM fortune WHERE id = $1";
+#[allow(dead_code)]
 pub const SELECT_WORLD_BY_ID: &str =
     "SELECT id, randomnumber FROM world WHERE id = $1 LIMIT 1";
 #[allow(dead_code)]
@@ -21,6 +51,25 @@ pub const UPDATE_WORLDS: &str = r#"UPDATE world SET randomnumber = new.rnum FROM
     (SELECT * FROM UNNEST($1::int[], $2::int[]) AS v(id, rnum) ORDER BY 1) AS new
 WHERE world.id = new.id"#;
 
+// `/api/worlds`, on the `ApiWorld` table apart from `World`.
+#[allow(dead_code)]
+pub const SELECT_API_WORLD: &str = "SELECT id, randomnumber FROM apiworld WHERE id = $1";
+/// Keyset pagination by `id > $1`, which is also used with `$1 = 0` for `OFFSET`.
+#[allow(dead_code)]
+pub const LIST_API_WORLDS: &str =
+    "SELECT id, randomnumber FROM apiworld WHERE id > $1 ORDER BY id LIMIT $2 OFFSET $3";
+/// Return no row if the id is taken.
+#[allow(dead_code)]
+pub const INSERT_API_WORLD: &str = r#"INSERT INTO apiworld (id, randomnumber)
+    VALUES (COALESCE($1::int, nextval(pg_get_serial_sequence('apiworld', 'id'))::int), $2)
+    ON CONFLICT (id) DO NOTHING
+    RETURNING id, randomnumber"#;
+/// Keep the random number if `$2` is `NULL`.
+#[allow(dead_code)]
+pub const UPDATE_API_WORLD: &str = "UPDATE apiworld SET randomnumber = COALESCE($2::int, randomnumber) WHERE id = $1 RETURNING id, randomnumber";
+#[allow(dead_code)]
+pub const DELETE_API_WORLD: &str = "DELETE FROM apiworld WHERE id = $1";
+
 /// Return the value of an environment variable.
 #[allow(dead_code)]
 pub fn get_env<T: FromStr>(key: &str) -> T
@@ -33,6 +82,69 @@ where
         .unwrap_or_else(|_| panic!("could not parse {key}"))
 }
 
+/// Return the value of an environment variable, or `default` if it was not set.
+#[allow(dead_code)]
+pub fn get_env_or<T: FromStr>(key: &str, default: T) -> T
+where
+    <T as FromStr>::Err: Debug,
+{
+    match env::var(key) {
+        Ok(value) => value
+            .parse::<T>()
+            .unwrap_or_else(|_| panic!("could not parse {key}")),
+        Err(_) => default,
+    }
+}
+
+/// Negotiate `Accept-Encoding` (gzip, br, zstd) for a route, with the
+/// `compression` feature. Bodies smaller than `COMPRESSION_MIN_SIZE` bytes
+/// (1024 by default) are sent as they are.
+#[inline(always)]
+pub fn compressed<S>(route: MethodRouter<S>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    #[cfg(feature = "compression")]
+    let route = {
+        use tower_http::compression::{predicate::SizeAbove, CompressionLayer};
+
+        route.layer(
+            CompressionLayer::new()
+                .compress_when(SizeAbove::new(get_env_or("COMPRESSION_MIN_SIZE", 1024))),
+        )
+    };
+
+    route
+}
+
+/// Whether the request body is declared as JSON (`application/json` or `application/*+json`).
+#[cfg(any(feature = "simd-json", feature = "sonic-rs"))]
+pub fn json_content_type(headers: &axum::http::HeaderMap) -> bool {
+    let content_type = if let Some(content_type) = headers.get(axum::http::header::CONTENT_TYPE) {
+        content_type
+    } else {
+        return false;
+    };
+
+    let content_type = if let Ok(content_type) = content_type.to_str() {
+        content_type
+    } else {
+        return false;
+    };
+
+    let mime = if let Ok(mime) = content_type.parse::<mime::Mime>() {
+        mime
+    } else {
+        return false;
+    };
+
+    let is_json_content_type = mime.type_() == "application"
+        && (mime.subtype() == "json"
+            || mime.suffix().map_or(false, |name| name == "json"));
+
+    is_json_content_type
+}
+
 /// Generate a single integer in the range 1 to 10,000 (inclusive)
 #[allow(dead_code)]
 #[inline(always)]
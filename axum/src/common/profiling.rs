@@ -0,0 +1,97 @@
+//! On-demand CPU profiling of the whole process (`pprof` feature),
+//! served at `/debug/pprof/profile?seconds=N&format=(flamegraph|proto)`.
+//!
+//! The sampling is signal-based and process-wide, so all the per-core
+//! runtime threads are profiled, whichever one serves the request.
+
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+const DEFAULT_SECONDS: u64 = 10;
+const MAX_SECONDS: u64 = 300;
+const FREQUENCY: i32 = 999;
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileParams {
+    seconds: Option<u64>,
+    format: Option<String>,
+}
+
+enum Format {
+    Flamegraph,
+    Proto,
+}
+
+enum ProfileError {
+    /// Another profile is running.
+    Busy(String),
+    Report(String),
+}
+
+/// `GET /debug/pprof/profile`
+pub async fn profile(Query(params): Query<ProfileParams>) -> Response {
+    let seconds = match params.seconds.unwrap_or(DEFAULT_SECONDS) {
+        s @ 1..=MAX_SECONDS => s,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("`seconds` must be in 1..={MAX_SECONDS}"),
+            )
+                .into_response()
+        }
+    };
+    let format = match params.format.as_deref() {
+        None | Some("flamegraph") => Format::Flamegraph,
+        Some("proto") => Format::Proto,
+        Some(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "`format` must be `flamegraph` or `proto`",
+            )
+                .into_response()
+        }
+    };
+
+    // The profiler isn't `Send`; let a blocking thread hold it while sleeping.
+    let profiled = tokio::task::spawn_blocking(move || {
+        let report = |e: pprof::Error| ProfileError::Report(e.to_string());
+
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(|e| ProfileError::Busy(e.to_string()))?;
+
+        std::thread::sleep(std::time::Duration::from_secs(seconds));
+
+        let profile = guard.report().build().map_err(report)?;
+        let mut body = Vec::new();
+        match format {
+            Format::Flamegraph => {
+                profile.flamegraph(&mut body).map_err(report)?;
+                Ok(("image/svg+xml", body))
+            }
+            Format::Proto => {
+                use pprof::protos::Message;
+                profile
+                    .pprof()
+                    .map_err(report)?
+                    .write_to_vec(&mut body)
+                    .map_err(|e| ProfileError::Report(e.to_string()))?;
+                Ok(("application/octet-stream", body))
+            }
+        }
+    })
+    .await
+    .expect("profiling task panicked");
+
+    match profiled {
+        Ok((content_type, body)) => ([(header::CONTENT_TYPE, content_type)], body).into_response(),
+        Err(ProfileError::Busy(e)) => (StatusCode::CONFLICT, e).into_response(),
+        Err(ProfileError::Report(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
@@ -0,0 +1,215 @@
+//! Per-statement timings of the Postgres layer (`query-stats` feature).
+//!
+//! Each runtime keeps its own histograms, registered globally so that
+//! `/admin/query-stats` and the shutdown summary can report all of them.
+//! Executions slower than `SLOW_QUERY_THRESHOLD_MS` (100 by default) are
+//! logged with their parameters.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+
+use axum::http::{header, HeaderValue};
+use axum::response::IntoResponse;
+
+use super::get_env_or;
+
+#[derive(Clone, Copy)]
+pub enum Statement {
+    SelectWorldById,
+    SelectAllFortunes,
+    SelectFortuneById,
+    UpdateWorlds,
+    SelectApiWorld,
+    ListApiWorlds,
+    InsertApiWorld,
+    UpdateApiWorld,
+    DeleteApiWorld,
+}
+
+impl Statement {
+    const ALL: [Self; 9] = [
+        Self::SelectWorldById,
+        Self::SelectAllFortunes,
+        Self::SelectFortuneById,
+        Self::UpdateWorlds,
+        Self::SelectApiWorld,
+        Self::ListApiWorlds,
+        Self::InsertApiWorld,
+        Self::UpdateApiWorld,
+        Self::DeleteApiWorld,
+    ];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::SelectWorldById => "select_world_by_id",
+            Self::SelectAllFortunes => "select_all_fortunes",
+            Self::SelectFortuneById => "select_fortune_by_id",
+            Self::UpdateWorlds => "update_worlds",
+            Self::SelectApiWorld => "select_api_world",
+            Self::ListApiWorlds => "list_api_worlds",
+            Self::InsertApiWorld => "insert_api_world",
+            Self::UpdateApiWorld => "update_api_world",
+            Self::DeleteApiWorld => "delete_api_world",
+        }
+    }
+}
+
+static REGISTRY: Mutex<Vec<Arc<QueryStats>>> = Mutex::new(Vec::new());
+
+/// Statement timings of one runtime.
+pub struct QueryStats {
+    runtime: String,
+    slow_threshold: Duration,
+    histograms: [Histogram; Statement::ALL.len()],
+}
+
+impl QueryStats {
+    /// Create the stats of the current runtime and register them.
+    pub fn register() -> Arc<Self> {
+        let stats = Arc::new(Self {
+            runtime: std::thread::current()
+                .name()
+                .unwrap_or("unnamed")
+                .to_string(),
+            slow_threshold: Duration::from_millis(get_env_or("SLOW_QUERY_THRESHOLD_MS", 100)),
+            histograms: Default::default(),
+        });
+        REGISTRY.lock().unwrap().push(stats.clone());
+        stats
+    }
+
+    /// Record an execution. `params` is only evaluated when it was slow.
+    #[inline]
+    pub fn record(&self, statement: Statement, elapsed: Duration, params: impl FnOnce() -> String) {
+        self.histograms[statement as usize].record(elapsed);
+        if elapsed >= self.slow_threshold {
+            eprintln!(
+                "[slow query] {}: {} took {elapsed:?} with {}",
+                self.runtime,
+                statement.name(),
+                params()
+            );
+        }
+    }
+}
+
+/// `GET /admin/query-stats`
+pub async fn query_stats() -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )],
+        summary(),
+    )
+}
+
+/// JSON summary of all the registered runtimes, and of them all merged as `"all"`.
+pub fn summary() -> String {
+    let registry = REGISTRY.lock().unwrap();
+
+    let mut all = [Snapshot::default(); Statement::ALL.len()];
+    let mut runtimes = Vec::with_capacity(registry.len() + 1);
+    for stats in registry.iter() {
+        let snapshots = stats.histograms.each_ref().map(Histogram::snapshot);
+        for (all, snapshot) in all.iter_mut().zip(&snapshots) {
+            all.merge(snapshot);
+        }
+        runtimes.push((stats.runtime.as_str(), snapshots));
+    }
+    runtimes.push(("all", all));
+
+    let runtimes = runtimes
+        .into_iter()
+        .map(|(runtime, snapshots)| {
+            let statements = Statement::ALL
+                .iter()
+                .map(|s| format!("\"{}\":{}", s.name(), snapshots[*s as usize].json()))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("\"{runtime}\":{{{statements}}}")
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{{runtimes}}}")
+}
+
+/// Bucket `i` counts the executions that took less than `2^i` µs
+/// (and at least `2^(i-1)` µs).
+const BUCKETS: usize = 32;
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    count: AtomicU64,
+    sum_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+impl Histogram {
+    #[inline]
+    fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        let i = (u64::BITS - us.leading_zeros()) as usize;
+        self.buckets[i.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            buckets: self.buckets.each_ref().map(|b| b.load(Ordering::Relaxed)),
+            count: self.count.load(Ordering::Relaxed),
+            sum_us: self.sum_us.load(Ordering::Relaxed),
+            max_us: self.max_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Snapshot {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+
+impl Snapshot {
+    fn merge(&mut self, other: &Self) {
+        for (b, o) in self.buckets.iter_mut().zip(&other.buckets) {
+            *b += o;
+        }
+        self.count += other.count;
+        self.sum_us += other.sum_us;
+        self.max_us = self.max_us.max(other.max_us);
+    }
+
+    /// Upper bound of the bucket containing the `p`-th percentile.
+    fn percentile_us(&self, p: u64) -> u64 {
+        let target = (self.count * p).div_ceil(100);
+        let mut seen = 0;
+        for (i, b) in self.buckets.iter().enumerate() {
+            seen += b;
+            if seen >= target {
+                return (1_u64 << i).min(self.max_us);
+            }
+        }
+        self.max_us
+    }
+
+    fn json(&self) -> String {
+        format!(
+            "{{\"count\":{},\"mean_us\":{},\"p50_us\":{},\"p90_us\":{},\"p99_us\":{},\"max_us\":{}}}",
+            self.count,
+            self.sum_us.checked_div(self.count).unwrap_or(0),
+            self.percentile_us(50),
+            self.percentile_us(90),
+            self.percentile_us(99),
+            self.max_us,
+        )
+    }
+}
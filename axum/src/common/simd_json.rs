@@ -5,24 +5,32 @@ use axum::{async_trait, http};
 use axum_core::response::{IntoResponse, Response};
 use bytes::{BufMut, Bytes, BytesMut};
 use http::{
-    header::{self, HeaderMap, HeaderValue},
+    header::{self, HeaderValue},
     StatusCode,
 };
 use serde::{de::DeserializeOwned, Serialize};
 use simd_json;
 
+use super::json_content_type;
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Json<T>(pub T);
 
 pub enum SimdJsonRejection {
     Json(JsonRejection),
     Bytes(BytesRejection),
-    Simd(String),
+    /// A syntax error (`400 Bad Request`), or a body that doesn't match the
+    /// target type (`422 Unprocessable Entity`), as axum's `Json` tells them.
+    Simd(StatusCode, String),
 }
 
 impl IntoResponse for SimdJsonRejection {
     fn into_response(self) -> Response {
-        todo!()
+        match self {
+            SimdJsonRejection::Json(rejection) => rejection.into_response(),
+            SimdJsonRejection::Bytes(rejection) => rejection.into_response(),
+            SimdJsonRejection::Simd(status, message) => (status, message).into_response(),
+        }
     }
 }
 
@@ -40,7 +48,12 @@ impl From<BytesRejection> for SimdJsonRejection {
 
 impl From<simd_json::Error> for SimdJsonRejection {
     fn from(err: simd_json::Error) -> Self {
-        SimdJsonRejection::Simd(err.to_string())
+        let status = if err.is_data() {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        SimdJsonRejection::Simd(status, err.to_string())
     }
 }
 
@@ -64,32 +77,6 @@ where
     }
 }
 
-fn json_content_type(headers: &HeaderMap) -> bool {
-    let content_type = if let Some(content_type) = headers.get(header::CONTENT_TYPE) {
-        content_type
-    } else {
-        return false;
-    };
-
-    let content_type = if let Ok(content_type) = content_type.to_str() {
-        content_type
-    } else {
-        return false;
-    };
-
-    let mime = if let Ok(mime) = content_type.parse::<mime::Mime>() {
-        mime
-    } else {
-        return false;
-    };
-
-    let is_json_content_type = mime.type_() == "application"
-        && (mime.subtype() == "json"
-            || mime.suffix().map_or(false, |name| name == "json"));
-
-    is_json_content_type
-}
-
 axum_core::__impl_deref!(Json);
 
 impl<T> From<T> for Json<T> {
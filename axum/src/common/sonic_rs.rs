@@ -0,0 +1,106 @@
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection::MissingJsonContentType;
+use axum::extract::rejection::{BytesRejection, JsonRejection};
+use axum::extract::{FromRequest, Request};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_core::response::{IntoResponse, Response};
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::json_content_type;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+pub enum SonicJsonRejection {
+    Json(JsonRejection),
+    Bytes(BytesRejection),
+    /// A syntax error (`400 Bad Request`), or a body that doesn't match the
+    /// target type (`422 Unprocessable Entity`), as axum's `Json` tells them.
+    Sonic(StatusCode, String),
+}
+
+impl IntoResponse for SonicJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            SonicJsonRejection::Json(rejection) => rejection.into_response(),
+            SonicJsonRejection::Bytes(rejection) => rejection.into_response(),
+            SonicJsonRejection::Sonic(status, message) => (status, message).into_response(),
+        }
+    }
+}
+
+impl From<BytesRejection> for SonicJsonRejection {
+    fn from(err: BytesRejection) -> Self {
+        SonicJsonRejection::Bytes(err)
+    }
+}
+
+impl From<sonic_rs::Error> for SonicJsonRejection {
+    fn from(err: sonic_rs::Error) -> Self {
+        let status = if err.is_data() {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        SonicJsonRejection::Sonic(status, err.to_string())
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = SonicJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if json_content_type(req.headers()) {
+            let bytes = Bytes::from_request(req, state).await?;
+            Ok(Json(sonic_rs::from_slice(&bytes)?))
+        } else {
+            Err(SonicJsonRejection::Json(MissingJsonContentType(
+                axum::extract::rejection::MissingJsonContentType::default(),
+            )))
+        }
+    }
+}
+
+axum_core::__impl_deref!(Json);
+
+impl<T> From<T> for Json<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        // Use a small initial capacity of 128 bytes like serde_json::to_vec
+        // https://docs.rs/serde_json/1.0.82/src/serde_json/ser.rs.html#2189
+        let mut buf = BytesMut::with_capacity(128).writer();
+        match sonic_rs::to_writer(&mut buf, &self.0) {
+            Ok(()) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+                )],
+                buf.into_inner().freeze(),
+            )
+                .into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
+                )],
+                err.to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
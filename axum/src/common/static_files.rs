@@ -0,0 +1,144 @@
+//! `GET /static/:name` serves the files of `STATIC_DIR` (`../static` by
+//! default, generated by `gen-static.sh`) with `Content-Length`, a single
+//! `Range` (206, or 416 if unsatisfiable), and `ETag`/`Last-Modified`
+//! validation by `If-None-Match`/`If-Modified-Since` (304).
+//!
+//! Only one path segment is served, and the (ranged) content is read into
+//! memory per request, as ohkami can do no better: so no `sendfile` either.
+
+use std::{
+    io::SeekFrom,
+    path::{Component, Path as FsPath, PathBuf},
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Body,
+    extract::Path,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use super::get_env_or;
+
+static STATIC_DIR: LazyLock<PathBuf> =
+    LazyLock::new(|| get_env_or("STATIC_DIR", String::from("../static")).into());
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// `None` for anything but a single `bytes=` range, which is ignored and
+/// the whole file is served. `Some(None)` if the range is unsatisfiable.
+fn byte_range(range: &str, len: u64) -> Option<Option<(u64, u64)>> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    if end.contains(',') {
+        return None;
+    }
+    let last = len.saturating_sub(1);
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return None,
+        ("", suffix) => (len.saturating_sub(suffix.parse().ok()?), last),
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(last)),
+    };
+    Some((start <= end && start < len).then_some((start, end)))
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+pub async fn static_file(Path(name): Path<String>, headers: HeaderMap) -> Response {
+    let path = FsPath::new(&name);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let Ok(mut file) = tokio::fs::File::open(STATIC_DIR.join(path)).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let metadata = file.metadata().await.expect("error reading file metadata");
+    if !metadata.is_file() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let len = metadata.len();
+    // In whole seconds, as `Last-Modified`.
+    let mtime_secs = metadata
+        .modified()
+        .ok()
+        .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    let mtime = UNIX_EPOCH + Duration::from_secs(mtime_secs);
+
+    let etag = format!(r#""{mtime_secs:x}-{len:x}""#);
+    let last_modified = httpdate::fmt_http_date(mtime);
+
+    let not_modified = match header_str(&headers, header::IF_NONE_MATCH) {
+        Some(tags) => tags
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag),
+        None => header_str(&headers, header::IF_MODIFIED_SINCE)
+            .and_then(|since| httpdate::parse_http_date(since).ok())
+            .is_some_and(|since: SystemTime| mtime <= since),
+    };
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::LAST_MODIFIED, last_modified)],
+        )
+            .into_response();
+    }
+
+    let range = header_str(&headers, header::RANGE).and_then(|range| byte_range(range, len));
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, len.saturating_sub(1)),
+        Some(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end),
+        Some(None) => {
+            return (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{len}"))],
+            )
+                .into_response();
+        }
+    };
+    let content_length = if len == 0 { 0 } else { end - start + 1 };
+
+    let mut content = vec![0; content_length as usize];
+    file.seek(SeekFrom::Start(start))
+        .await
+        .expect("error seeking file");
+    file.read_exact(&mut content)
+        .await
+        .expect("error reading file");
+
+    let mut response = (
+        status,
+        [
+            (header::CONTENT_TYPE, content_type(&name).to_string()),
+            (header::ACCEPT_RANGES, String::from("bytes")),
+            (header::ETAG, etag),
+            (header::LAST_MODIFIED, last_modified),
+        ],
+        Body::from(content),
+    )
+        .into_response();
+    if range.is_some() {
+        response.headers_mut().insert(
+            header::CONTENT_RANGE,
+            format!("bytes {start}-{end}/{len}").parse().unwrap(),
+        );
+    }
+    response
+}
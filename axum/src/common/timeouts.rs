@@ -0,0 +1,125 @@
+//! Timeouts of `serve_hyper`, all disabled by default:
+//!
+//! * `HEADER_READ_TIMEOUT_MS`: to read the head of a request, over HTTP/1 only.
+//!   Hyper also runs it while waiting for the next request on a kept-alive
+//!   connection, which is closed past it.
+//! * `KEEP_ALIVE_TIMEOUT_MS`: connections with no request in flight for this
+//!   long are shut down gracefully. Hyper waits for a request whose head is
+//!   being read, so only the header read timeout reaps slow heads.
+//! * `REQUEST_TIMEOUT_MS`: to read the body of a request and handle it.
+//!   Past it, the request is answered `408 Request Timeout`.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use hyper_util::server::{conn::auto, graceful::GracefulConnection};
+use tower_http::timeout::TimeoutLayer;
+
+use super::get_env_or;
+
+#[derive(Clone, Copy)]
+pub struct Timeouts {
+    pub header_read: Option<Duration>,
+    pub keep_alive: Option<Duration>,
+    pub request: Option<Duration>,
+}
+
+impl Timeouts {
+    pub fn from_env() -> Self {
+        let millis = |key| match get_env_or(key, 0) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        };
+        Self {
+            header_read: millis("HEADER_READ_TIMEOUT_MS"),
+            keep_alive: millis("KEEP_ALIVE_TIMEOUT_MS"),
+            request: millis("REQUEST_TIMEOUT_MS"),
+        }
+    }
+
+    /// Set the header read timeout to the connection builder.
+    pub fn configure(&self, builder: &mut auto::Builder<TokioExecutor>) {
+        if let Some(timeout) = self.header_read {
+            builder
+                .http1()
+                .timer(TokioTimer::new())
+                .header_read_timeout(timeout);
+        }
+    }
+
+    pub fn request_layer(&self) -> Option<TimeoutLayer> {
+        self.request
+            .map(|timeout| TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, timeout))
+    }
+}
+
+/// The requests in flight on a connection, to tell how long it has been idle.
+pub struct KeepAlive {
+    timeout: Duration,
+    in_flight: AtomicUsize,
+    idle_since: Mutex<Instant>,
+}
+
+/// Counts a request as in flight until dropped.
+pub struct InFlight(Arc<KeepAlive>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        *self.0.idle_since.lock().unwrap() = Instant::now();
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl KeepAlive {
+    pub fn new(timeout: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            timeout,
+            in_flight: AtomicUsize::new(0),
+            idle_since: Mutex::new(Instant::now()),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>) -> InFlight {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight(self.clone())
+    }
+
+    /// When the connection times out if no request comes, `None` while one is in flight.
+    fn deadline(&self) -> Option<Instant> {
+        (self.in_flight.load(Ordering::Relaxed) == 0)
+            .then(|| *self.idle_since.lock().unwrap() + self.timeout)
+    }
+}
+
+/// Serve `connection` until it's done, shutting it down gracefully once it's
+/// idle for the timeout of `keep_alive` if any. Responses being written are
+/// finished first.
+pub async fn serve_until_idle<C: GracefulConnection>(
+    connection: C,
+    keep_alive: Option<Arc<KeepAlive>>,
+) -> Result<(), C::Error> {
+    let Some(keep_alive) = keep_alive else {
+        return connection.await;
+    };
+
+    tokio::pin!(connection);
+    loop {
+        let deadline = keep_alive
+            .deadline()
+            .unwrap_or_else(|| Instant::now() + keep_alive.timeout);
+        tokio::select! {
+            result = connection.as_mut() => return result,
+            () = tokio::time::sleep_until(deadline.into()) => {
+                if keep_alive.deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+                    connection.as_mut().graceful_shutdown();
+                    return connection.await;
+                }
+            }
+        }
+    }
+}
@@ -20,6 +20,30 @@ pub fn parse_params(params: Params) -> usize {
         .clamp(1, 500)
 }
 
+#[cfg(feature = "sse")]
+#[derive(Debug, Deserialize)]
+pub struct SseParams {
+    events: Option<String>,
+    interval_ms: Option<String>,
+}
+
+/// Number of events (1..=100000, 10 by default) and the interval between
+/// them (up to 60s, 100ms by default).
+#[cfg(feature = "sse")]
+pub fn parse_sse_params(params: SseParams) -> (usize, std::time::Duration) {
+    let events = params
+        .events
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(10)
+        .clamp(1, 100_000);
+    let interval_ms = params
+        .interval_ms
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or(100)
+        .min(60_000);
+    (events, std::time::Duration::from_millis(interval_ms))
+}
+
 /// Utility function for mapping any error into a `500 Internal Server Error`
 /// response.
 #[allow(dead_code)]
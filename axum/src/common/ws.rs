@@ -0,0 +1,74 @@
+//! WebSocket endpoints (`ws` feature):
+//!
+//! * `/ws/echo` sends every text or binary message back to its sender.
+//! * `/ws/broadcast` relays every text message to all the connected sockets,
+//!   including the sender, across all the runtimes.
+
+use std::sync::LazyLock;
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::get_env_or;
+
+/// Messages kept for slow subscribers (`WS_BROADCAST_CAPACITY`, 1024 by default).
+/// A subscriber lagging behind more than this skips the oldest ones.
+static BROADCAST: LazyLock<broadcast::Sender<String>> =
+    LazyLock::new(|| broadcast::channel(get_env_or("WS_BROADCAST_CAPACITY", 1024)).0);
+
+pub async fn echo(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(|mut socket: WebSocket| async move {
+        while let Some(Ok(message)) = socket.recv().await {
+            let sent = match message {
+                Message::Text(_) | Message::Binary(_) => socket.send(message).await,
+                Message::Close(_) => break,
+                Message::Ping(_) | Message::Pong(_) => Ok(()),
+            };
+            if sent.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+pub async fn broadcast(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(|socket: WebSocket| async move {
+        let mut subscription = BROADCAST.subscribe();
+        let (mut sink, mut stream) = socket.split();
+
+        let publish = async {
+            while let Some(Ok(message)) = stream.next().await {
+                match message {
+                    Message::Text(text) => {
+                        let _ = BROADCAST.send(text);
+                    }
+                    Message::Close(_) => break,
+                    _ => (),
+                }
+            }
+        };
+        let relay = async {
+            loop {
+                match subscription.recv().await {
+                    Ok(text) => {
+                        if sink.send(Message::Text(text)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => (),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        };
+
+        // Whichever ends first closes the connection.
+        tokio::select! {
+            _ = publish => (),
+            _ = relay => (),
+        }
+    })
+}
@@ -1,35 +1,60 @@
+// The 64 nested pairs of `middleware-stack` are deeper than the default limit.
+#![cfg_attr(feature = "middleware-stack", recursion_limit = "256")]
+
+mod alloc;
+mod api;
 mod common;
 mod pg;
+#[cfg(feature = "deadpool")]
+mod pg_pool;
+#[cfg(feature = "sqlx")]
+mod pg_sqlx;
 
 use axum::{
-    extract::Query, http::StatusCode, response::IntoResponse, routing::get, Router,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
 };
 use dotenv::dotenv;
 use rand::{rngs::SmallRng, thread_rng, SeedableRng};
-use yarte::Template;
-use mimalloc::MiMalloc;
-
-#[global_allocator]
-static GLOBAL: MiMalloc = MiMalloc;
 
-#[cfg(not(feature = "simd-json"))]
+#[cfg(not(any(feature = "simd-json", feature = "sonic-rs", feature = "itoa-json")))]
 use axum::Json;
+#[cfg(feature = "itoa-json")]
+use common::itoa_json::Json;
 #[cfg(feature = "simd-json")]
 use common::simd_json::Json;
+#[cfg(feature = "sonic-rs")]
+use common::sonic_rs::Json;
 
 mod server;
+mod templates;
+#[cfg(feature = "tls")]
+mod tls;
 
 use common::{
-    get_env, random_id,
+    get_env,
+    models::Message,
+    random_id,
     utils::{parse_params, Params, Utf8Html},
 };
+#[cfg(not(any(feature = "sqlx", feature = "deadpool")))]
 use pg::database::{DatabaseConnection, PgConnection};
-use pg::models::Fortune;
+#[cfg(feature = "deadpool")]
+use pg_pool::database::{DatabaseConnection, PgConnection};
+#[cfg(feature = "sqlx")]
+use pg_sqlx::database::{DatabaseConnection, PgConnection};
+use pg::models::{Fortune, World};
+use templates::FortunesTemplate;
+
+async fn json() -> impl IntoResponse {
+    let message = Message {
+        message: "Hello, World!",
+    };
 
-#[derive(Template)]
-#[template(path = "fortunes.html.hbs")]
-pub struct FortunesTemplate<'a> {
-    pub fortunes: &'a Vec<Fortune>,
+    (StatusCode::OK, Json(message))
 }
 
 async fn db(DatabaseConnection(conn): DatabaseConnection) -> impl IntoResponse {
@@ -58,16 +83,17 @@ async fn queries(
 }
 
 async fn fortunes(DatabaseConnection(conn): DatabaseConnection) -> impl IntoResponse {
-    let fortunes: Vec<Fortune> = conn
+    let rows = conn
         .fetch_all_fortunes()
         .await
         .expect("error loading fortunes");
+    let fortunes: Vec<Fortune> = rows.fortunes();
 
     Utf8Html(
         FortunesTemplate {
             fortunes: &fortunes,
         }
-        .call()
+        .render()
         .expect("error rendering template"),
     )
 }
@@ -82,23 +108,180 @@ async fn updates(
     (StatusCode::OK, Json(worlds))
 }
 
+async fn worlds_batch(
+    DatabaseConnection(conn): DatabaseConnection,
+    Json(worlds): Json<Vec<World>>,
+) -> Response {
+    let (ids, nids) = match World::validate_batch(&worlds) {
+        Ok(batch) => batch,
+        Err(message) => return (StatusCode::UNPROCESSABLE_ENTITY, message).into_response(),
+    };
+
+    conn.save_worlds(&ids, &nids)
+        .await
+        .expect("error saving worlds");
+
+    (StatusCode::OK, Json(worlds)).into_response()
+}
+
+async fn world_by_id(
+    DatabaseConnection(conn): DatabaseConnection,
+    Path(id): Path<u32>,
+) -> Response {
+    let Ok(id @ 1..=10_000) = i32::try_from(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let world = conn
+        .fetch_world_by_id(id)
+        .await
+        .expect("error loading world");
+
+    (StatusCode::OK, Json(world)).into_response()
+}
+
+async fn fortune_by_id(
+    DatabaseConnection(conn): DatabaseConnection,
+    Path(id): Path<u32>,
+) -> Response {
+    let Ok(id) = i32::try_from(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let rows = conn
+        .fetch_fortune_by_id(id)
+        .await
+        .expect("error loading fortune");
+
+    // Serialize while `rows` is still alive.
+    match rows.first() {
+        Some(fortune) => (StatusCode::OK, Json(fortune)).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Only exercises the router's path parameters, with no I/O.
+async fn nested_params(Path((x, y, z)): Path<(String, String, String)>) -> String {
+    format!("x={x}, y={y}, z={z}")
+}
+
+/// Stream `events` random worlds as JSON events, one every `interval_ms`.
+#[cfg(feature = "sse")]
+async fn sse(
+    DatabaseConnection(conn): DatabaseConnection,
+    Query(params): Query<common::utils::SseParams>,
+) -> impl IntoResponse {
+    use axum::response::sse::{Event, Sse};
+    use std::convert::Infallible;
+
+    let (events, interval) = common::utils::parse_sse_params(params);
+    let rng = SmallRng::from_rng(&mut thread_rng()).unwrap();
+
+    let stream = futures::stream::unfold((conn, rng, 0), move |(conn, mut rng, i)| async move {
+        if i == events {
+            return None;
+        }
+        if i > 0 && !interval.is_zero() {
+            tokio::time::sleep(interval).await;
+        }
+        let world = conn
+            .fetch_world_by_id(random_id(&mut rng))
+            .await
+            .expect("error loading world");
+        let event = Event::default()
+            .json_data(world)
+            .expect("error serializing world");
+
+        Some((Ok::<_, Infallible>(event), (conn, rng, i + 1)))
+    });
+
+    Sse::new(stream)
+}
+
 fn main() {
     dotenv().ok();
+
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_thread_names(true)
+        .with_thread_ids(true)
+        .init();
+
+    if let Ok(iterations) = std::env::var("RENDER_BENCH") {
+        return templates::bench_render(iterations.parse().expect("invalid RENDER_BENCH"));
+    }
+
     server::start_tokio(serve_app)
 }
 
+/// Print the query stats summary when the server is stopped.
+#[cfg(feature = "query-stats")]
+async fn print_query_stats_on_shutdown() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).unwrap();
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => (),
+        _ = terminate.recv() => (),
+    }
+    println!("{}", common::query_stats::summary());
+    std::process::exit(0)
+}
+
 async fn serve_app() {
+    #[cfg(feature = "query-stats")]
+    {
+        // Only one of the runtimes has to wait for the signal.
+        static SHUTDOWN_HOOK: std::sync::Once = std::sync::Once::new();
+        SHUTDOWN_HOOK.call_once(|| {
+            tokio::spawn(print_query_stats_on_shutdown());
+        });
+    }
+
     let database_url: String = get_env("POSTGRES_URL");
 
     // Create shared database connection
     let pg_connection = PgConnection::connect(database_url).await;
 
     let app = Router::new()
-        .route("/fortunes", get(fortunes))
+        .route("/json", get(json))
+        .route("/fortunes", common::compressed(get(fortunes)))
         .route("/db", get(db))
-        .route("/queries", get(queries))
-        .route("/updates", get(updates))
-        .with_state(pg_connection);
+        .route("/queries", common::compressed(get(queries)))
+        .route("/updates", common::compressed(get(updates)))
+        .route("/worlds/batch", post(worlds_batch))
+        .route("/worlds/:id", get(world_by_id))
+        .route("/fortunes/:id", get(fortune_by_id))
+        .route("/a/:x/b/:y/c/:z", get(nested_params))
+        .merge(api::router());
+
+    #[cfg(feature = "sse")]
+    let app = app.route("/sse", get(sse));
+
+    #[cfg(feature = "static-files")]
+    let app = app.route("/static/:name", get(common::static_files::static_file));
+
+    #[cfg(feature = "many-routes")]
+    let app = app.merge(common::many_routes::router());
+
+    let app = app.with_state(pg_connection);
+
+    #[cfg(feature = "query-stats")]
+    let app = app.route(
+        "/admin/query-stats",
+        get(common::query_stats::query_stats),
+    );
+
+    #[cfg(feature = "pprof")]
+    let app = app.route("/debug/pprof/profile", get(common::profiling::profile));
+
+    #[cfg(feature = "ws")]
+    let app = app
+        .route("/ws/echo", get(common::ws::echo))
+        .route("/ws/broadcast", get(common::ws::broadcast));
 
-    server::serve_hyper(app, Some(8000)).await
+    #[cfg(not(feature = "middleware-stack"))]
+    server::serve_hyper(app, Some(8000)).await;
+    #[cfg(feature = "middleware-stack")]
+    server::serve_hyper_with(app, Some(8000), common::middleware_stack::stack).await;
 }
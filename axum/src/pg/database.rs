@@ -4,11 +4,13 @@ use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
 use futures::{stream::futures_unordered::FuturesUnordered, StreamExt, TryStreamExt};
 use rand::{rngs::SmallRng, thread_rng, SeedableRng};
 use tokio::pin;
-use tokio_postgres::{connect, Client, NoTls, Statement};
+use tokio_postgres::{connect, Client, Statement};
 
+#[cfg(feature = "query-stats")]
+use crate::common::query_stats::{QueryStats, Statement};
 use crate::common::{self, random_id, random_ids};
 
-use super::models::{Fortune, World};
+use super::models::{FortuneRows, World};
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -29,17 +31,30 @@ impl From<tokio_postgres::Error> for PgError {
     }
 }
 
+/// Prepared statements of `/api/worlds`.
+struct CrudStatements {
+    select: Statement,
+    list: Statement,
+    insert: Statement,
+    update: Statement,
+    delete: Statement,
+}
+
 /// Postgres interface
 pub struct PgConnection {
     client: Client,
     fortune: Statement,
+    fortune_by_id: Statement,
     world: Statement,
     updates: Statement,
+    crud: CrudStatements,
+    #[cfg(feature = "query-stats")]
+    stats: Arc<QueryStats>,
 }
 
 impl PgConnection {
     pub async fn connect(db_url: String) -> Arc<PgConnection> {
-        let (cl, conn) = connect(&db_url, NoTls)
+        let (cl, conn) = connect(&db_url, super::connector())
             .await
             .expect("cannot connect to postgresql.");
 
@@ -52,29 +67,53 @@ impl PgConnection {
 
         // Prepare statements for the connection.
         let fortune = cl.prepare(common::SELECT_ALL_FORTUNES).await.unwrap();
+        let fortune_by_id = cl.prepare(common::SELECT_FORTUNE_BY_ID).await.unwrap();
         let world = cl.prepare(common::SELECT_WORLD_BY_ID).await.unwrap();
         let updates = cl.prepare(common::UPDATE_WORLDS).await.unwrap();
 
+        let crud = CrudStatements {
+            select: cl.prepare(common::SELECT_API_WORLD).await.unwrap(),
+            list: cl.prepare(common::LIST_API_WORLDS).await.unwrap(),
+            insert: cl.prepare(common::INSERT_API_WORLD).await.unwrap(),
+            update: cl.prepare(common::UPDATE_API_WORLD).await.unwrap(),
+            delete: cl.prepare(common::DELETE_API_WORLD).await.unwrap(),
+        };
+
         Arc::new(PgConnection {
             client: cl,
             fortune,
+            fortune_by_id,
             world,
             updates,
+            crud,
+            #[cfg(feature = "query-stats")]
+            stats: QueryStats::register(),
         })
     }
 }
 
 impl PgConnection {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_world_by_id", skip(self))
+    )]
     pub async fn fetch_world_by_id(&self, id: i32) -> Result<World, PgError> {
-        self.client
-            .query_one(&self.world, &[&id])
-            .await
-            .map(|row| {
-                Ok(World {
-                    id: row.get(0),
-                    randomnumber: row.get(1),
-                })
-            })?
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let row = self.client.query_one(&self.world, &[&id]).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::SelectWorldById, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        row.map(|row| {
+            Ok(World {
+                id: row.get(0),
+                randomnumber: row.get(1),
+            })
+        })?
     }
 
     pub async fn fetch_random_worlds(&self, num: usize) -> Result<Vec<World>, PgError> {
@@ -104,19 +143,40 @@ impl PgConnection {
         }
 
         // Update the random worlds in the database.
-        self.client
-            .execute(&self.updates, &[&ids, &nids])
-            .await
-            .unwrap();
+        self.save_worlds(&ids, &nids).await?;
 
         Ok(worlds)
     }
 
-    pub async fn fetch_all_fortunes(&self) -> Result<Vec<Fortune>, PgError> {
-        let mut fortunes = vec![Fortune {
-            id: 0,
-            message: "Additional fortune added at request time.".parse().unwrap(),
-        }];
+    /// Save the random numbers `nids` of the worlds `ids` with the batched
+    /// `UPDATE ... UNNEST` statement.
+    pub async fn save_worlds(&self, ids: &[i32], nids: &[i32]) -> Result<(), PgError> {
+        let update = self.client.execute(&self.updates, &[&ids, &nids]);
+        #[cfg(feature = "tracing")]
+        let update = tracing::Instrument::instrument(
+            update,
+            tracing::info_span!("update_worlds", num = ids.len()),
+        );
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        update.await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::UpdateWorlds, start.elapsed(), || {
+            format!("$1 = {ids:?}, $2 = {nids:?}")
+        });
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_all_fortunes", skip(self))
+    )]
+    pub async fn fetch_all_fortunes(&self) -> Result<FortuneRows, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
 
         let rows = self
             .client
@@ -125,15 +185,159 @@ impl PgConnection {
 
         pin!(rows);
 
+        let mut fortune_rows = Vec::new();
         while let Some(row) = rows.next().await.transpose()? {
-            fortunes.push(Fortune {
-                id: row.get(0),
-                message: row.get(1),
-            });
+            fortune_rows.push(row);
         }
 
-        fortunes.sort_by(|it, next| it.message.cmp(&next.message));
-        Ok(fortunes)
+        #[cfg(feature = "query-stats")]
+        self.stats
+            .record(Statement::SelectAllFortunes, start.elapsed(), String::new);
+
+        Ok(FortuneRows(fortune_rows))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_fortune_by_id", skip(self))
+    )]
+    pub async fn fetch_fortune_by_id(&self, id: i32) -> Result<FortuneRows, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let rows = self.client.query(&self.fortune_by_id, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::SelectFortuneById, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(FortuneRows(rows))
+    }
+}
+
+/// `/api/worlds` (see `crate::api`).
+impl PgConnection {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_api_world", skip(self))
+    )]
+    pub async fn select_api_world(&self, id: i32) -> Result<Option<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let result = self.client.query_opt(&self.crud.select, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::SelectApiWorld, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(result.as_ref().map(world))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "list_api_worlds", skip(self))
+    )]
+    pub async fn list_api_worlds(
+        &self,
+        after: i32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let result = self
+            .client
+            .query(&self.crud.list, &[&after, &limit, &offset])
+            .await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::ListApiWorlds, start.elapsed(), || {
+            format!("$1 = {after}, $2 = {limit}, $3 = {offset}")
+        });
+
+        Ok(result.iter().map(world).collect())
+    }
+
+    /// Insert a world, returning `None` if `id` is taken.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "insert_api_world", skip(self))
+    )]
+    pub async fn insert_api_world(
+        &self,
+        id: Option<i32>,
+        randomnumber: i32,
+    ) -> Result<Option<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let result = self
+            .client
+            .query_opt(&self.crud.insert, &[&id, &randomnumber])
+            .await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::InsertApiWorld, start.elapsed(), || {
+            format!("$1 = {id:?}, $2 = {randomnumber}")
+        });
+
+        Ok(result.as_ref().map(world))
+    }
+
+    /// Update a world, returning `None` if there's no such world.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "update_api_world", skip(self))
+    )]
+    pub async fn update_api_world(
+        &self,
+        id: i32,
+        randomnumber: Option<i32>,
+    ) -> Result<Option<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let result = self
+            .client
+            .query_opt(&self.crud.update, &[&id, &randomnumber])
+            .await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::UpdateApiWorld, start.elapsed(), || {
+            format!("$1 = {id}, $2 = {randomnumber:?}")
+        });
+
+        Ok(result.as_ref().map(world))
+    }
+
+    /// Delete a world, returning whether it existed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "delete_api_world", skip(self))
+    )]
+    pub async fn delete_api_world(&self, id: i32) -> Result<bool, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let result = self.client.execute(&self.crud.delete, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::DeleteApiWorld, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(result > 0)
+    }
+}
+
+fn world(row: &tokio_postgres::Row) -> World {
+    World {
+        id: row.get(0),
+        randomnumber: row.get(1),
     }
 }
 
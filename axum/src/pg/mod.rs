@@ -1,2 +1,17 @@
+#[cfg(all(feature = "sqlx", feature = "deadpool"))]
+compile_error!("select at most one Postgres driver of `sqlx` and `deadpool`");
+
+#[cfg(not(any(feature = "sqlx", feature = "deadpool")))]
 pub mod database;
 pub mod models;
+
+#[cfg(all(feature = "postgres-tls", not(feature = "sqlx")))]
+pub mod tls;
+#[cfg(all(feature = "postgres-tls", not(feature = "sqlx")))]
+pub use tls::connector;
+/// TLS connector of tokio-postgres (and deadpool), or none without the `postgres-tls` feature.
+/// sqlx negotiates TLS by itself, with `sslmode` and `sslrootcert` in `POSTGRES_URL`.
+#[cfg(not(any(feature = "postgres-tls", feature = "sqlx")))]
+pub fn connector() -> tokio_postgres::NoTls {
+    tokio_postgres::NoTls
+}
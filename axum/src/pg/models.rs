@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
 
+/// A fortune borrowing its `message` from the row it was decoded from.
 #[allow(non_snake_case)]
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
-pub struct Fortune {
+pub struct Fortune<'r> {
     pub id: i32,
-    pub message: String,
+    pub message: &'r str,
 }
 
 #[allow(non_snake_case)]
@@ -14,3 +15,176 @@ pub struct World {
     #[serde(rename = "randomNumber")]
     pub randomnumber: i32,
 }
+
+/// Most worlds accepted by `POST /worlds/batch`, as by `/updates`.
+pub const MAX_BATCH: usize = 500;
+
+impl World {
+    /// Check a batch of worlds to save, returning their ids and random numbers
+    /// sorted by id, so that concurrent batches lock their rows in the same
+    /// order rather than deadlock. Both must be in 1..=10000, and each id may
+    /// only appear once.
+    pub fn validate_batch(worlds: &[World]) -> Result<(Vec<i32>, Vec<i32>), String> {
+        if worlds.is_empty() || worlds.len() > MAX_BATCH {
+            return Err(format!(
+                "expected 1 to {MAX_BATCH} worlds, got {}",
+                worlds.len()
+            ));
+        }
+
+        let mut pairs = Vec::with_capacity(worlds.len());
+        for w in worlds {
+            if !(1..=10_000).contains(&w.id) || !(1..=10_000).contains(&w.randomnumber) {
+                return Err(format!(
+                    "`id` and `randomNumber` must be in 1..=10000, got {} and {}",
+                    w.id, w.randomnumber
+                ));
+            }
+            pairs.push((w.id, w.randomnumber));
+        }
+
+        pairs.sort_unstable_by_key(|&(id, _)| id);
+        if let Some(dup) = pairs.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("duplicate id {}", dup[0].0));
+        }
+
+        Ok(pairs.into_iter().unzip())
+    }
+}
+
+/// Body of `POST /api/worlds` and `PUT`/`PATCH /api/worlds/:id`.
+#[derive(Debug, Deserialize)]
+pub struct WorldInput {
+    id: Option<i32>,
+    #[serde(rename = "randomNumber")]
+    randomnumber: Option<i32>,
+}
+
+impl WorldInput {
+    fn check_randomnumber(randomnumber: i32) -> Result<i32, String> {
+        match randomnumber {
+            n @ 1..=10_000 => Ok(n),
+            n => Err(format!("`randomNumber` must be in 1..=10000, got {n}")),
+        }
+    }
+
+    fn check_id(&self, path_id: i32) -> Result<(), String> {
+        match self.id {
+            Some(id) if id != path_id => {
+                Err(format!("`id` {id} doesn't match the path's {path_id}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The id (assigned by Postgres if omitted) and the random number of a new world.
+    pub fn validate_new(&self) -> Result<(Option<i32>, i32), String> {
+        if let Some(id @ ..=0) = self.id {
+            return Err(format!("`id` must be positive, got {id}"));
+        }
+        let randomnumber = self.randomnumber.ok_or("`randomNumber` is required")?;
+        Ok((self.id, Self::check_randomnumber(randomnumber)?))
+    }
+
+    /// The random number replacing the world `id`'s.
+    pub fn validate_put(&self, id: i32) -> Result<i32, String> {
+        self.check_id(id)?;
+        let randomnumber = self.randomnumber.ok_or("`randomNumber` is required")?;
+        Self::check_randomnumber(randomnumber)
+    }
+
+    /// The random number to set to the world `id`, if any.
+    pub fn validate_patch(&self, id: i32) -> Result<Option<i32>, String> {
+        self.check_id(id)?;
+        self.randomnumber.map(Self::check_randomnumber).transpose()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorldsPageParams {
+    limit: Option<String>,
+    offset: Option<String>,
+    after: Option<String>,
+}
+
+impl WorldsPageParams {
+    /// `(after, limit, offset)` of `GET /api/worlds`: either `offset`
+    /// (0 by default) or the keyset cursor `after` (the last id of the
+    /// previous page), with `limit` in 1..=100 (20 by default).
+    pub fn parse(&self) -> Result<(i32, i64, i64), String> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("invalid `{name}`: {value}"))
+        }
+
+        let limit = match self
+            .limit
+            .as_deref()
+            .map(|l| parse::<i64>("limit", l))
+            .transpose()?
+        {
+            None => 20,
+            Some(l @ 1..=100) => l,
+            Some(l) => return Err(format!("`limit` must be in 1..=100, got {l}")),
+        };
+        match (self.after.as_deref(), self.offset.as_deref()) {
+            (Some(_), Some(_)) => Err("`after` and `offset` can't be used together".into()),
+            (Some(after), None) => Ok((parse("after", after)?, limit, 0)),
+            (None, offset) => match offset.map(|o| parse::<i64>("offset", o)).transpose()? {
+                Some(o @ ..0) => Err(format!("`offset` must not be negative, got {o}")),
+                o => Ok((0, limit, o.unwrap_or(0))),
+            },
+        }
+    }
+}
+
+/// A page of `GET /api/worlds`, with the cursor of the next one
+/// if it may not be the last.
+#[derive(Debug, Serialize)]
+pub struct WorldsPage {
+    pub worlds: Vec<World>,
+    pub next_after: Option<i32>,
+}
+
+/// Raw fortune rows. `Fortune`s borrow their messages from these buffers,
+/// so they must outlive rendering.
+pub struct FortuneRows(
+    #[cfg(not(feature = "sqlx"))] pub(crate) Vec<tokio_postgres::Row>,
+    #[cfg(feature = "sqlx")] pub(crate) Vec<sqlx::postgres::PgRow>,
+);
+
+impl FortuneRows {
+    /// Decode the rows, add the request-time fortune and sort by message.
+    pub fn fortunes(&self) -> Vec<Fortune<'_>> {
+        #[cfg(feature = "sqlx")]
+        use sqlx::Row as _;
+
+        let mut fortunes = Vec::with_capacity(self.0.len() + 1);
+        fortunes.push(Fortune {
+            id: 0,
+            message: "Additional fortune added at request time.",
+        });
+
+        for row in &self.0 {
+            fortunes.push(Fortune {
+                id: row.get(0),
+                message: row.get(1),
+            });
+        }
+
+        fortunes.sort_by(|it, next| it.message.cmp(next.message));
+        fortunes
+    }
+
+    /// Decode the first row only, as fetched by id.
+    pub fn first(&self) -> Option<Fortune<'_>> {
+        #[cfg(feature = "sqlx")]
+        use sqlx::Row as _;
+
+        self.0.first().map(|row| Fortune {
+            id: row.get(0),
+            message: row.get(1),
+        })
+    }
+}
@@ -0,0 +1,348 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use deadpool_postgres::{Manager, ManagerConfig, Pool, PoolError, RecyclingMethod};
+use futures::{stream::futures_unordered::FuturesUnordered, TryStreamExt};
+use rand::{rngs::SmallRng, thread_rng, SeedableRng};
+
+#[cfg(feature = "query-stats")]
+use crate::common::query_stats::{QueryStats, Statement};
+use crate::common::{self, get_env_or, random_id, random_ids};
+use crate::pg::models::{FortuneRows, World};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum PgError {
+    Pool(PoolError),
+    Pg(tokio_postgres::Error),
+}
+
+impl From<PoolError> for PgError {
+    fn from(err: PoolError) -> Self {
+        PgError::Pool(err)
+    }
+}
+
+impl From<tokio_postgres::Error> for PgError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        PgError::Pg(err)
+    }
+}
+
+/// Postgres interface backed by a deadpool of tokio-postgres connections.
+/// Statements are prepared once per connection and cached by deadpool.
+/// With `query-stats`, a statement is timed from the connection checkout,
+/// as the sqlx pool acquires within its queries and ohkami times its driver
+/// calls as a whole.
+pub struct PgConnection {
+    pool: Pool,
+    #[cfg(feature = "query-stats")]
+    stats: Arc<QueryStats>,
+}
+
+impl PgConnection {
+    pub async fn connect(db_url: String) -> Arc<PgConnection> {
+        let manager = Manager::from_config(
+            db_url.parse().expect("invalid postgresql url."),
+            crate::pg::connector(),
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Fast,
+            },
+        );
+
+        // Pool size per runtime.
+        let max_connections = get_env_or("MAX_CONNECTIONS", 4);
+        let min_connections = get_env_or("MIN_CONNECTIONS", 4);
+        // The warmup would wait forever for connections over the pool size.
+        assert!(
+            min_connections <= max_connections,
+            "MIN_CONNECTIONS ({min_connections}) must not exceed MAX_CONNECTIONS ({max_connections})"
+        );
+        let pool = Pool::builder(manager)
+            .max_size(max_connections)
+            .build()
+            .expect("cannot build connection pool.");
+
+        // Open `MIN_CONNECTIONS` connections in advance.
+        let mut warmup = Vec::new();
+        for _ in 0..min_connections {
+            warmup.push(pool.get().await.expect("cannot connect to postgresql."));
+        }
+        drop(warmup);
+
+        Arc::new(PgConnection {
+            pool,
+            #[cfg(feature = "query-stats")]
+            stats: QueryStats::register(),
+        })
+    }
+}
+
+impl PgConnection {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_world_by_id", skip(self))
+    )]
+    pub async fn fetch_world_by_id(&self, id: i32) -> Result<World, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let world = client.prepare_cached(common::SELECT_WORLD_BY_ID).await?;
+
+        let row = client.query_one(&world, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::SelectWorldById, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(World {
+            id: row.get(0),
+            randomnumber: row.get(1),
+        })
+    }
+
+    pub async fn fetch_random_worlds(&self, num: usize) -> Result<Vec<World>, PgError> {
+        let mut rng = SmallRng::from_rng(&mut thread_rng()).unwrap();
+
+        let futures = FuturesUnordered::new();
+
+        for id in random_ids(&mut rng, num) {
+            futures.push(self.fetch_world_by_id(id));
+        }
+
+        futures.try_collect().await
+    }
+
+    pub async fn update_worlds(&self, num: usize) -> Result<Vec<World>, PgError> {
+        let mut worlds = self.fetch_random_worlds(num).await?;
+
+        // Update the worlds with new random numbers
+        let mut rng = SmallRng::from_rng(&mut thread_rng()).unwrap();
+        let mut ids = Vec::with_capacity(num);
+        let mut nids = Vec::with_capacity(num);
+
+        for w in &mut worlds {
+            w.randomnumber = random_id(&mut rng);
+            ids.push(w.id);
+            nids.push(w.randomnumber);
+        }
+
+        // Update the random worlds in the database.
+        self.save_worlds(&ids, &nids).await?;
+
+        Ok(worlds)
+    }
+
+    /// Save the random numbers `nids` of the worlds `ids` with the batched
+    /// `UPDATE ... UNNEST` statement.
+    pub async fn save_worlds(&self, ids: &[i32], nids: &[i32]) -> Result<(), PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let updates = client.prepare_cached(common::UPDATE_WORLDS).await?;
+        let update = client.execute(&updates, &[&ids, &nids]);
+        #[cfg(feature = "tracing")]
+        let update = tracing::Instrument::instrument(
+            update,
+            tracing::info_span!("update_worlds", num = ids.len()),
+        );
+
+        update.await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::UpdateWorlds, start.elapsed(), || {
+            format!("$1 = {ids:?}, $2 = {nids:?}")
+        });
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_all_fortunes", skip(self))
+    )]
+    pub async fn fetch_all_fortunes(&self) -> Result<FortuneRows, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let fortune = client.prepare_cached(common::SELECT_ALL_FORTUNES).await?;
+
+        let rows = client.query(&fortune, &[]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats
+            .record(Statement::SelectAllFortunes, start.elapsed(), String::new);
+
+        Ok(FortuneRows(rows))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_fortune_by_id", skip(self))
+    )]
+    pub async fn fetch_fortune_by_id(&self, id: i32) -> Result<FortuneRows, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let fortune = client.prepare_cached(common::SELECT_FORTUNE_BY_ID).await?;
+
+        let rows = client.query(&fortune, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::SelectFortuneById, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(FortuneRows(rows))
+    }
+}
+
+/// `/api/worlds` (see `crate::api`).
+impl PgConnection {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "select_api_world", skip(self))
+    )]
+    pub async fn select_api_world(&self, id: i32) -> Result<Option<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let statement = client.prepare_cached(common::SELECT_API_WORLD).await?;
+
+        let result = client.query_opt(&statement, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::SelectApiWorld, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(result.as_ref().map(world))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "list_api_worlds", skip(self))
+    )]
+    pub async fn list_api_worlds(
+        &self,
+        after: i32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let statement = client.prepare_cached(common::LIST_API_WORLDS).await?;
+
+        let result = client.query(&statement, &[&after, &limit, &offset]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::ListApiWorlds, start.elapsed(), || {
+            format!("$1 = {after}, $2 = {limit}, $3 = {offset}")
+        });
+
+        Ok(result.iter().map(world).collect())
+    }
+
+    /// Insert a world, returning `None` if `id` is taken.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "insert_api_world", skip(self))
+    )]
+    pub async fn insert_api_world(
+        &self,
+        id: Option<i32>,
+        randomnumber: i32,
+    ) -> Result<Option<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let statement = client.prepare_cached(common::INSERT_API_WORLD).await?;
+
+        let result = client.query_opt(&statement, &[&id, &randomnumber]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::InsertApiWorld, start.elapsed(), || {
+            format!("$1 = {id:?}, $2 = {randomnumber}")
+        });
+
+        Ok(result.as_ref().map(world))
+    }
+
+    /// Update a world, returning `None` if there's no such world.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "update_api_world", skip(self))
+    )]
+    pub async fn update_api_world(
+        &self,
+        id: i32,
+        randomnumber: Option<i32>,
+    ) -> Result<Option<World>, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let statement = client.prepare_cached(common::UPDATE_API_WORLD).await?;
+
+        let result = client.query_opt(&statement, &[&id, &randomnumber]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::UpdateApiWorld, start.elapsed(), || {
+            format!("$1 = {id}, $2 = {randomnumber:?}")
+        });
+
+        Ok(result.as_ref().map(world))
+    }
+
+    /// Delete a world, returning whether it existed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "delete_api_world", skip(self))
+    )]
+    pub async fn delete_api_world(&self, id: i32) -> Result<bool, PgError> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let client = self.pool.get().await?;
+        let statement = client.prepare_cached(common::DELETE_API_WORLD).await?;
+
+        let result = client.execute(&statement, &[&id]).await?;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(Statement::DeleteApiWorld, start.elapsed(), || {
+            format!("$1 = {id}")
+        });
+
+        Ok(result > 0)
+    }
+}
+
+fn world(row: &tokio_postgres::Row) -> World {
+    World {
+        id: row.get(0),
+        randomnumber: row.get(1),
+    }
+}
+
+pub struct DatabaseConnection(pub Arc<PgConnection>);
+
+#[async_trait]
+impl FromRequestParts<Arc<PgConnection>> for DatabaseConnection {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        _parts: &mut Parts,
+        pg_connection: &Arc<PgConnection>,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(pg_connection.clone()))
+    }
+}
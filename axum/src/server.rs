@@ -1,22 +1,33 @@
 use std::{
+    convert::Infallible,
     future::Future,
     io,
     net::{Ipv4Addr, SocketAddr, TcpListener},
+    sync::{Arc, LazyLock},
 };
 
 use axum::{
     http::{header, HeaderValue},
+    response::Response,
     Router,
 };
 
 use hyper::body::Incoming;
 use hyper::Request;
 use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tower::Service;
 use tower_http::set_header::SetResponseHeaderLayer;
 
 use socket2::{Domain, Socket, Type};
 
+use crate::common::{
+    get_env_or,
+    limits::{ConnectionLimit, InFlightLimitLayer},
+    timeouts::{self, KeepAlive, Timeouts},
+};
+
 /// Reuse an existing listener, ensuring that the socket `backlog``
 /// is set to enable a higher number of pending connections.
 fn set_socket_options(addr: SocketAddr) -> io::Result<tokio::net::TcpListener> {
@@ -36,6 +47,41 @@ fn set_socket_options(addr: SocketAddr) -> io::Result<tokio::net::TcpListener> {
     tokio::net::TcpListener::from_std(listener)
 }
 
+/// Open a `request` span per request, recording the matched route, the
+/// status and the latency. Postgres statements are traced as its child spans.
+#[cfg(feature = "tracing")]
+fn trace(app: Router<()>) -> Router<()> {
+    use axum::{body::Body, extract::MatchedPath, http::Response};
+    use std::time::Duration;
+    use tower_http::trace::TraceLayer;
+    use tracing::{field::Empty, Span};
+
+    app.layer(
+        TraceLayer::new_for_http()
+            .make_span_with(|request: &Request<Body>| {
+                let route = request
+                    .extensions()
+                    .get::<MatchedPath>()
+                    .map_or(request.uri().path(), MatchedPath::as_str);
+                tracing::info_span!(
+                    "request",
+                    method = request.method().as_str(),
+                    route,
+                    status = Empty,
+                    latency_us = Empty,
+                )
+            })
+            .on_request(())
+            .on_response(
+                |response: &Response<Body>, latency: Duration, span: &Span| {
+                    span.record("status", response.status().as_u16());
+                    span.record("latency_us", latency.as_micros() as u64);
+                    tracing::info!("response");
+                },
+            ),
+    )
+}
+
 /// Build an Axum server with consistent configuration, using the high-level API exposed
 /// by Axum 0.7. This is intended for convenience and intentionally does not provide much
 /// customisability.
@@ -52,26 +98,186 @@ pub async fn serve(app: Router<()>, port: Option<u16>) {
         server_header_value,
     ));
 
+    #[cfg(feature = "access-log")]
+    let app = app.layer(crate::common::access_log::AccessLogLayer::new());
+
+    #[cfg(feature = "tracing")]
+    let app = trace(app);
+
+    #[cfg(feature = "alloc-count")]
+    let app = app.layer(crate::alloc::counting::CountAllocsLayer);
+
     axum::serve(listener, app.into_make_service())
         .await
         .unwrap();
 }
 
+/// Build the connection builder of `serve_hyper`, serving the HTTP versions
+/// selected by `HTTP_MODE` at startup:
+/// * `http1` (default): HTTP/1 only.
+/// * `h2c`: HTTP/2 over cleartext, with prior knowledge only.
+/// * `auto`: HTTP/1, or HTTP/2 when the connection starts with its preface.
+///
+/// HTTP/2 requires the `http2` feature, and is tuned by `H2_MAX_CONCURRENT_STREAMS`,
+/// `H2_INITIAL_STREAM_WINDOW_SIZE` and `H2_INITIAL_CONNECTION_WINDOW_SIZE`.
+/// Hyper doesn't support the `Upgrade: h2c` mechanism (deprecated by RFC 9113),
+/// so such requests are answered over HTTP/1.1, ignoring the upgrade.
+fn connection_builder() -> auto::Builder<TokioExecutor> {
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder.http1().pipeline_flush(true);
+
+    let mode: String = get_env_or("HTTP_MODE", String::from("http1"));
+    match mode.as_str() {
+        #[cfg(not(feature = "http2"))]
+        "http1" => (),
+        #[cfg(feature = "http2")]
+        "http1" => builder = builder.http1_only(),
+        #[cfg(feature = "http2")]
+        "h2c" | "auto" => {
+            if mode == "h2c" {
+                builder = builder.http2_only();
+            }
+            builder
+                .http2()
+                .max_concurrent_streams(get_env_or("H2_MAX_CONCURRENT_STREAMS", 256))
+                .initial_stream_window_size(get_env_or("H2_INITIAL_STREAM_WINDOW_SIZE", 65_535))
+                .initial_connection_window_size(get_env_or(
+                    "H2_INITIAL_CONNECTION_WINDOW_SIZE",
+                    65_535,
+                ));
+        }
+        other if cfg!(not(feature = "http2")) => {
+            panic!("HTTP_MODE `{other}` requires the `http2` feature")
+        }
+        other => panic!("unknown HTTP_MODE `{other}`"),
+    }
+
+    builder
+}
+
+/// The Unix domain socket at `UNIX_SOCKET`, if set, with its permissions set
+/// to `UNIX_SOCKET_MODE` (octal, `660` by default). It's bound once and shared
+/// by all runtimes, as `SO_REUSEPORT` doesn't apply to Unix sockets.
+static UNIX_LISTENER: LazyLock<Option<std::os::unix::net::UnixListener>> = LazyLock::new(|| {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    let path = std::env::var("UNIX_SOCKET").ok()?;
+
+    // Remove a stale socket left by a previous run, but nothing else.
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        assert!(
+            metadata.file_type().is_socket(),
+            "UNIX_SOCKET `{path}` exists and is not a socket"
+        );
+        std::fs::remove_file(&path).expect("couldn't remove the stale Unix socket");
+    }
+
+    let listener =
+        std::os::unix::net::UnixListener::bind(&path).expect("couldn't bind to the Unix socket");
+    listener.set_nonblocking(true).unwrap();
+
+    let mode: String = get_env_or("UNIX_SOCKET_MODE", String::from("660"));
+    let mode = u32::from_str_radix(&mode, 8).expect("could not parse UNIX_SOCKET_MODE");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+        .expect("couldn't set the Unix socket permissions");
+
+    Some(listener)
+});
+
+/// Serve a single accepted connection, over TLS with the `tls` feature.
+async fn serve_connection<S, T>(
+    socket: S,
+    #[cfg_attr(not(feature = "access-log"), allow(unused_variables))] remote_addr: SocketAddr,
+    tower_service: T,
+    builder: Arc<auto::Builder<TokioExecutor>>,
+    #[cfg(feature = "tls")] acceptor: tokio_rustls::TlsAcceptor,
+    timeouts: Timeouts,
+    // Counts the connection against `MAX_CLIENT_CONNECTIONS` until it's closed.
+    _permit: Option<tokio::sync::OwnedSemaphorePermit>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T: Service<Request<Incoming>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    T::Future: Send + 'static,
+{
+    // The handshake counts against the header read timeout.
+    #[cfg(feature = "tls")]
+    let Ok(Ok(socket)) = tokio::time::timeout(
+        timeouts.header_read.unwrap_or(std::time::Duration::MAX),
+        acceptor.accept(socket),
+    )
+    .await
+    else {
+        return;
+    };
+    let socket = TokioIo::new(socket);
+
+    let keep_alive = timeouts.keep_alive.map(KeepAlive::new);
+    let in_flight = keep_alive.clone();
+    let hyper_service = hyper::service::service_fn(move |request: Request<Incoming>| {
+        let in_flight = in_flight.as_ref().map(KeepAlive::start);
+        #[cfg(feature = "access-log")]
+        let request = {
+            let mut request = request;
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(remote_addr));
+            request
+        };
+        let response = tower_service.clone().call(request);
+        async move {
+            let _in_flight = in_flight;
+            response.await
+        }
+    });
+
+    #[cfg(not(feature = "ws"))]
+    let connection = builder.serve_connection(socket, hyper_service);
+    #[cfg(feature = "ws")]
+    let connection = builder.serve_connection_with_upgrades(socket, hyper_service);
+    if (timeouts::serve_until_idle(connection, keep_alive).await).is_err() {}
+}
+
 /// Build an Axum server using the lower-level Hyper APIs for more
 /// configurability. This has a few optimisations, including:
-/// * Serving HTTP/1 only.
-/// * Disabling connection upgrades (websockets are not needed).
+/// * Serving HTTP/1 only, unless selected otherwise (see `connection_builder`).
+/// * Terminating TLS in-process with the `tls` feature (see `tls`).
+/// * Disabling connection upgrades, unless websockets are served (`ws` feature).
 /// * Setting TCP_NODELAY on the input stream.
 /// * Aggregating flushes to better support pipelined responses.
+/// * Capping connections and in-flight requests per runtime, if set (see `limits`).
+/// * Timing out slow or idle clients, if set (see `timeouts`).
+///
+/// It listens on the Unix domain socket at `UNIX_SOCKET` instead of `port` if
+/// set, where peers are recorded as `0.0.0.0:0` by the access log.
 ///
 /// See for more details:
 /// * https://github.com/tokio-rs/axum/blob/1ac617a1b540e8523347f5ee889d65cad9a45ec4/examples/serve-with-hyper/src/main.rs
 #[allow(dead_code)]
 pub async fn serve_hyper(app: Router<()>, port: Option<u16>) {
-    let port = port.unwrap_or(8000);
-    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
-    let listener = set_socket_options(addr).expect("couldn't bind to address");
-    println!("started axum server on port {port}.");
+    serve_hyper_with(app, port, |app| app).await
+}
+
+/// `serve_hyper`, serving `wrap(app)` once the layers above are applied to `app`.
+#[allow(dead_code)]
+pub async fn serve_hyper_with<T>(
+    app: Router<()>,
+    port: Option<u16>,
+    wrap: impl FnOnce(Router<()>) -> T,
+) where
+    T: Service<Request<Incoming>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    T::Future: Send + 'static,
+{
+    // Innermost, so that timed out and rejected requests are logged and get
+    // the `Server` header.
+    let timeouts = Timeouts::from_env();
+    let app = match timeouts.request_layer() {
+        Some(timeout) => app.layer(timeout),
+        None => app,
+    };
+    let app = match InFlightLimitLayer::from_env() {
+        Some(limit) => app.layer(limit),
+        None => app,
+    };
 
     let server_header_value = HeaderValue::from_static("Axum");
     let app = app.layer(SetResponseHeaderLayer::overriding(
@@ -79,30 +285,74 @@ pub async fn serve_hyper(app: Router<()>, port: Option<u16>) {
         server_header_value,
     ));
 
+    #[cfg(feature = "access-log")]
+    let app = app.layer(crate::common::access_log::AccessLogLayer::new());
+
+    #[cfg(feature = "tracing")]
+    let app = trace(app);
+
+    #[cfg(feature = "alloc-count")]
+    let app = app.layer(crate::alloc::counting::CountAllocsLayer);
+
+    let app = wrap(app);
+
+    let mut builder = connection_builder();
+    timeouts.configure(&mut builder);
+    let builder = Arc::new(builder);
+    #[cfg(feature = "tls")]
+    let acceptor = crate::tls::acceptor();
+    let connections = ConnectionLimit::from_env();
+
+    if let Some(listener) = UNIX_LISTENER.as_ref() {
+        let listener = listener
+            .try_clone()
+            .and_then(tokio::net::UnixListener::from_std)
+            .expect("couldn't listen on the Unix socket");
+        println!(
+            "started axum server on {:?}.",
+            listener.local_addr().unwrap()
+        );
+
+        // Continuously accept new connections.
+        loop {
+            let permit = connections.acquire().await;
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(serve_connection(
+                socket,
+                SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+                app.clone(),
+                builder.clone(),
+                #[cfg(feature = "tls")]
+                acceptor.clone(),
+                timeouts,
+                permit,
+            ));
+        }
+    }
+
+    let port = port.unwrap_or(8000);
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+    let listener = set_socket_options(addr).expect("couldn't bind to address");
+    println!("started axum server on port {port}.");
+
     // Continuously accept new connections.
     loop {
-        let (socket, _remote_addr) = listener.accept().await.unwrap();
+        let permit = connections.acquire().await;
+        let (socket, remote_addr) = listener.accept().await.unwrap();
         socket
             .set_nodelay(true)
             .expect("could not set TCP_NODELAY!");
 
-        let tower_service = app.clone();
-        tokio::spawn(async move {
-            let socket = TokioIo::new(socket);
-
-            let hyper_service =
-                hyper::service::service_fn(move |request: Request<Incoming>| {
-                    tower_service.clone().call(request)
-                });
-
-            if (hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
-                .http1()
-                .pipeline_flush(true)
-                .serve_connection(socket, hyper_service)
-                .await)
-                .is_err()
-            {}
-        });
+        tokio::spawn(serve_connection(
+            socket,
+            remote_addr,
+            app.clone(),
+            builder.clone(),
+            #[cfg(feature = "tls")]
+            acceptor.clone(),
+            timeouts,
+            permit,
+        ));
     }
 }
 
@@ -117,14 +367,17 @@ where
         .build()
         .unwrap();
 
-    for _ in 1..num_cpus::get() {
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-            rt.block_on(f());
-        });
+    for i in 1..num_cpus::get() {
+        std::thread::Builder::new()
+            .name(format!("runtime-{i}"))
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                rt.block_on(f());
+            })
+            .expect("could not spawn a runtime thread");
     }
     rt.block_on(f());
 }
@@ -0,0 +1,185 @@
+//! `/fortunes` rendering. The engine is selected by cargo features
+//! (`askama`, `sailfish`, `handwritten`), falling back to yarte.
+//! Template sources are shared with ohkami: `../templates` (see `yarte.toml`,
+//! `askama.toml` and `sailfish.toml`).
+
+use crate::pg::models::Fortune;
+
+#[cfg(any(
+    all(feature = "askama", feature = "sailfish"),
+    all(feature = "askama", feature = "handwritten"),
+    all(feature = "sailfish", feature = "handwritten"),
+))]
+compile_error!("select at most one template engine of `askama`, `sailfish` and `handwritten`");
+
+#[cfg(not(any(feature = "askama", feature = "sailfish", feature = "handwritten")))]
+#[derive(yarte::Template)]
+#[template(path = "fortunes.html.hbs")]
+pub struct FortunesTemplate<'a> {
+    pub fortunes: &'a Vec<Fortune<'a>>,
+}
+
+#[cfg(not(any(feature = "askama", feature = "sailfish", feature = "handwritten")))]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "yarte";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        yarte::Template::call(&self)
+    }
+}
+
+#[cfg(feature = "askama")]
+#[derive(askama::Template)]
+#[template(path = "fortunes.html")]
+pub struct FortunesTemplate<'a> {
+    pub fortunes: &'a Vec<Fortune<'a>>,
+}
+
+#[cfg(feature = "askama")]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "askama";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        askama::Template::render(&self).map_err(|_| std::fmt::Error)
+    }
+}
+
+#[cfg(feature = "sailfish")]
+#[derive(sailfish::TemplateOnce)]
+#[template(path = "fortunes.stpl")]
+pub struct FortunesTemplate<'a> {
+    pub fortunes: &'a Vec<Fortune<'a>>,
+}
+
+#[cfg(feature = "sailfish")]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "sailfish";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        sailfish::TemplateOnce::render_once(self).map_err(|_| std::fmt::Error)
+    }
+}
+
+#[cfg(feature = "handwritten")]
+pub struct FortunesTemplate<'a> {
+    pub fortunes: &'a Vec<Fortune<'a>>,
+}
+
+#[cfg(feature = "handwritten")]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "handwritten";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        const HEAD: &str = "<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table><tr><th>id</th><th>message</th></tr>";
+        const TAIL: &str = "</table></body></html>";
+
+        let mut html =
+            String::with_capacity(HEAD.len() + TAIL.len() + self.fortunes.len() * 64);
+        html.push_str(HEAD);
+        for fortune in self.fortunes {
+            html.push_str("<tr><td>");
+            html.push_str(itoa::Buffer::new().format(fortune.id));
+            html.push_str("</td><td>");
+            escape_html(fortune.message, &mut html);
+            html.push_str("</td></tr>");
+        }
+        html.push_str(TAIL);
+
+        Ok(html)
+    }
+}
+
+/// Escape the same characters as yarte (`<>&"'/`), so that this engine's page
+/// is byte-identical to yarte's. askama and sailfish don't escape `/`, and
+/// sailfish escapes `'` as `&#039;`: their pages differ (see `bench_render`).
+#[cfg(feature = "handwritten")]
+#[inline]
+fn escape_html(s: &str, buf: &mut String) {
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let escaped = match b {
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'&' => "&amp;",
+            b'"' => "&quot;",
+            b'\'' => "&#x27;",
+            b'/' => "&#x2f;",
+            _ => continue,
+        };
+        buf.push_str(&s[start..i]);
+        buf.push_str(escaped);
+        start = i + 1;
+    }
+    buf.push_str(&s[start..]);
+}
+
+/// yarte's page, which `bench_render` compares the selected engine's to.
+#[derive(yarte::Template)]
+#[template(path = "fortunes.html.hbs")]
+struct YartePage<'a> {
+    fortunes: &'a Vec<Fortune<'a>>,
+}
+
+/// Render the `postgres/init.sql` fortunes `iterations` times, as `/fortunes`
+/// does (request-time fortune and sort included) but without any HTTP or
+/// database involved, and print the average cost per render, the page's
+/// size and whether it's byte-identical to yarte's.
+pub fn bench_render(iterations: u32) {
+    let messages = include_str!("../../postgres/init.sql")
+        .lines()
+        .filter_map(|line| line.strip_prefix("INSERT INTO Fortune (id, message) VALUES ("))
+        .map(|values| {
+            let (id, message) = values.split_once(", '").unwrap();
+            let message = message.strip_suffix("');").unwrap().replace("''", "'");
+            (id.parse::<i32>().unwrap(), message)
+        })
+        .collect::<Vec<_>>();
+
+    let fortunes = || {
+        let mut fortunes = vec![Fortune {
+            id: 0,
+            message: "Additional fortune added at request time.",
+        }];
+        fortunes.extend(messages.iter().map(|(id, message)| Fortune {
+            id: *id,
+            message,
+        }));
+        fortunes.sort_by(|it, next| it.message.cmp(next.message));
+        fortunes
+    };
+
+    let (html, yarte) = {
+        let fortunes = fortunes();
+        let html = FortunesTemplate {
+            fortunes: &fortunes,
+        }
+        .render()
+        .unwrap();
+        let yarte = yarte::Template::call(&YartePage {
+            fortunes: &fortunes,
+        })
+        .unwrap();
+        (html, yarte)
+    };
+
+    let start = std::time::Instant::now();
+    for _ in 0..iterations {
+        let fortunes = fortunes();
+        let html = FortunesTemplate {
+            fortunes: &fortunes,
+        }
+        .render()
+        .unwrap();
+        std::hint::black_box(html);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{{\"engine\": \"{}\", \"iterations\": {iterations}, \"ns_per_render\": {:.1}, \"bytes_per_render\": {}, \"yarte_bytes\": {}, \"same_as_yarte\": {}}}",
+        FortunesTemplate::ENGINE,
+        elapsed.as_nanos() as f64 / iterations as f64,
+        html.len(),
+        yarte.len(),
+        html == yarte,
+    );
+}
@@ -0,0 +1,95 @@
+//! rustls acceptor for `serve_hyper` with the `tls` feature.
+//!
+//! The certificate chain and private key are loaded from the PEM files at
+//! `TLS_CERT` and `TLS_KEY`, or a self-signed certificate for `localhost` is
+//! generated at startup if they are not set. The acceptor is built once and
+//! shared by all runtimes, so that session resumption (by both the stateful
+//! cache and tickets) works across the `SO_REUSEPORT` listeners.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    sync::{Arc, LazyLock},
+};
+
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+    server::ServerSessionMemoryCache,
+    ServerConfig,
+};
+use tokio_rustls::TlsAcceptor;
+
+use crate::common::get_env_or;
+
+fn load_pem(
+    cert_path: &str,
+    key_path: &str,
+) -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(
+        File::open(cert_path).expect("could not open TLS_CERT"),
+    ))
+    .collect::<Result<Vec<_>, _>>()
+    .expect("invalid certificate in TLS_CERT");
+
+    let key = rustls_pemfile::private_key(&mut BufReader::new(
+        File::open(key_path).expect("could not open TLS_KEY"),
+    ))
+    .expect("invalid private key in TLS_KEY")
+    .expect("no private key in TLS_KEY");
+
+    (certs, key)
+}
+
+fn self_signed() -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+    println!(
+        "TLS_CERT and TLS_KEY are not set, generating a self-signed certificate for localhost."
+    );
+
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".into(), "127.0.0.1".into()])
+            .expect("could not generate a self-signed certificate");
+
+    (
+        vec![cert.der().clone()],
+        PrivatePkcs8KeyDer::from(key_pair.serialize_der()).into(),
+    )
+}
+
+/// ALPN protocols matching the HTTP versions served for `HTTP_MODE`.
+fn alpn_protocols() -> Vec<Vec<u8>> {
+    let mode: String = get_env_or("HTTP_MODE", String::from("http1"));
+    match mode.as_str() {
+        "h2c" => vec![b"h2".to_vec()],
+        "auto" => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+        _ => vec![b"http/1.1".to_vec()],
+    }
+}
+
+static ACCEPTOR: LazyLock<TlsAcceptor> = LazyLock::new(|| {
+    let (certs, key) = match (std::env::var("TLS_CERT"), std::env::var("TLS_KEY")) {
+        (Ok(cert_path), Ok(key_path)) => load_pem(&cert_path, &key_path),
+        (Err(_), Err(_)) => self_signed(),
+        _ => panic!("set both or neither of TLS_CERT and TLS_KEY"),
+    };
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("unsupported TLS protocol versions")
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .expect("invalid certificate or private key");
+
+    config.alpn_protocols = alpn_protocols();
+    config.session_storage =
+        ServerSessionMemoryCache::new(get_env_or("TLS_SESSION_CACHE_SIZE", 4096));
+    config.ticketer =
+        rustls::crypto::ring::Ticketer::new().expect("could not build the session ticketer");
+
+    TlsAcceptor::from(Arc::new(config))
+});
+
+/// The shared acceptor. Cloning it shares the session cache and the ticketer.
+pub fn acceptor() -> TlsAcceptor {
+    ACCEPTOR.clone()
+}
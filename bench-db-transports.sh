@@ -0,0 +1,32 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Compare the Postgres transports (TCP loopback, Unix socket and TLS) of a
+# framework on `/db` and `/updates`, logging each as `./bench.sh` does.
+
+if [ $# != 1 ]; then
+    echo 'usage: ./bench-db-transports.sh <framework>'
+    echo '       (set FEATURES to build the framework with more cargo features)'
+    exit 1
+fi
+framework="$1"
+
+export PATHS="${PATHS:-/db /updates?q=20}"
+
+for transport in tcp unix tls; do
+    features="${FEATURES:-}"
+    if [ "$transport" == 'tls' ]; then
+        features="${features:+$features }postgres-tls"
+    fi
+
+    echo "=== DB over $transport ==="
+    FEATURES="$features" DB_TRANSPORT="$transport" ./bench.sh "$framework" "db-$transport"
+done
+
+echo
+echo "Results:"
+for transport in tcp unix tls; do
+    log=$(ls -t ./.log/$framework-*-db-$transport.jsonc | head -n 1)
+    echo "$transport: $(tail -n +2 "$log" | jq -c .)"
+done
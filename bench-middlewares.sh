@@ -0,0 +1,48 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Compare middleware stack depths of a framework with the `middleware-stack`
+# feature: requests/sec of PATHS ('/plaintext' by default, apart from the
+# database) for each N in MIDDLEWARE_COUNTS ('0 1 4 16 64' by default)
+# pairs of no-op and header-setting middlewares, logged as `./bench.sh` does.
+# axum's stack is composed for 0, 1, 4, 8, 16 and 64 pairs only.
+
+if [ $# != 1 ]; then
+    echo 'usage: ./bench-middlewares.sh <framework>'
+    echo '       (set FEATURES to build the framework with more cargo features)'
+    exit 1
+fi
+framework="$1"
+
+read -r -a middleware_counts <<< "${MIDDLEWARE_COUNTS:-0 1 4 16 64}"
+features="${FEATURES:+$FEATURES }middleware-stack"
+
+export PATHS="${PATHS:-/plaintext}"
+
+result=''
+for n in "${middleware_counts[@]}"; do
+    echo "=== $n middlewares ==="
+    MIDDLEWARES=$n FEATURES="$features" ./bench.sh "$framework" "middlewares-$n"
+
+    log=$(ls -t ./.log/$framework-*-middlewares-$n.jsonc | head -n 1)
+    if [ "$result" != '' ]; then
+        result="$result,"
+    fi
+    result="$result{\"middlewares\": $n, \"rps\": $(tail -n +2 "$log" | jq -c .)}"
+done
+
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/$framework-$timestamp-middlewares.jsonc"
+# axum's stack is boxed once, at the top of the pairs
+boxed=''
+if [ "$framework" = 'axum' ]; then
+    boxed=', boxed once'
+fi
+echo "/* middleware stack depths$boxed${FEATURES:+ (features: $FEATURES)} */" >  $log_jsonc
+echo                                                                          >> $log_jsonc
+echo "[$result]" | jq                                                         >> $log_jsonc
+
+echo
+echo "Results:"
+tail -n +2 $log_jsonc | jq -c '.[]'
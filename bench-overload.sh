@@ -0,0 +1,66 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Compare how a framework degrades under increasing load, with and without
+# its per-runtime caps. For each number of `wrk` connections in LOADS
+# ('512 2048 8192' by default, mind `ulimit -n`), benchmarks PATHS
+# ('/db /fortunes' by default) once per limit in LIMITS:
+#
+#   none          no cap
+#   in-flight     MAX_IN_FLIGHT=OVERLOAD_MAX_IN_FLIGHT (64 by default)
+#   connections   MAX_CLIENT_CONNECTIONS=OVERLOAD_MAX_CONNECTIONS (128 by default, axum only)
+#
+# logging requests/sec, p99 latency, non-2xx responses (`503`s) and socket
+# errors per path (see `WRK_LATENCY` in `./bench.sh`).
+
+if [ $# != 1 ]; then
+    echo 'usage: ./bench-overload.sh <framework>'
+    echo '       (set FEATURES to build the framework with cargo features)'
+    exit 1
+fi
+framework="$1"
+
+read -r -a loads <<< "${LOADS:-512 2048 8192}"
+default_limits='none in-flight connections'
+if [ "$framework" == 'ohkami' ]; then
+    default_limits='none in-flight'
+fi
+read -r -a limits <<< "${LIMITS:-$default_limits}"
+
+export PATHS="${PATHS:-/db /fortunes}"
+export WRK_LATENCY=1
+
+result=''
+for load in "${loads[@]}"; do
+    for limit in "${limits[@]}"; do
+        echo "=== $load connections, limit: $limit ==="
+        limit_env=()
+        case "$limit" in
+            'none')        ;;
+            'in-flight')   limit_env=(MAX_IN_FLIGHT=${OVERLOAD_MAX_IN_FLIGHT:-64}) ;;
+            'connections') limit_env=(MAX_CLIENT_CONNECTIONS=${OVERLOAD_MAX_CONNECTIONS:-128}) ;;
+            *)
+                echo "unknown limit '$limit'"
+                exit 1
+                ;;
+        esac
+        env "${limit_env[@]}" WRK_CONNECTIONS=$load ./bench.sh "$framework" "overload-$load-$limit"
+
+        log=$(ls -t ./.log/$framework-*-overload-$load-$limit.jsonc | head -n 1)
+        if [ "$result" != '' ]; then
+            result="$result,"
+        fi
+        result="$result{\"connections\": $load, \"limit\": \"$limit\", \"paths\": $(tail -n +2 "$log" | jq -c .)}"
+    done
+done
+
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/$framework-$timestamp-overload.jsonc"
+echo "/* overload${FEATURES:+ (features: $FEATURES)} */" >  $log_jsonc
+echo                                                   >> $log_jsonc
+echo "[$result]" | jq                                  >> $log_jsonc
+
+echo
+echo "Results:"
+tail -n +2 $log_jsonc | jq -c '.[]'
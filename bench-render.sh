@@ -0,0 +1,40 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Measures `/fortunes` rendering per template engine, apart from
+# the HTTP framework and the database. Each line also reports the page's
+# size and whether it's byte-identical to yarte's: askama and sailfish
+# escape differently, so their pages are a few bytes shorter.
+
+iterations="${RENDER_BENCH:-100000}"
+engines=(
+    ''
+    'askama'
+    'sailfish'
+    'handwritten'
+)
+
+wd="$PWD"
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/render-$timestamp.jsonc"
+echo "/* fortunes rendering, $iterations iterations */" > $log_jsonc
+echo                                                    >> $log_jsonc
+
+result=''
+for framework in ohkami axum; do
+    for engine in "${engines[@]}"; do
+        cd ./$framework
+        line=$(RENDER_BENCH=$iterations cargo run --release --quiet ${engine:+--features "$engine"})
+        cd $wd
+
+        echo "$framework: $line"
+        if [ "$result" != '' ]; then
+            result="$result,"
+        fi
+        result="$result{\"framework\": \"$framework\", \"result\": $line}"
+    done
+done
+echo "[$result]" | jq >> $log_jsonc
+
+echo "Done !"
@@ -0,0 +1,57 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Compare route table sizes of a framework with the `many-routes` feature.
+# For each number of routes in ROUTE_COUNTS ('100 1000 10000' by default),
+# measures the release build of the crate itself (with its dependencies
+# already built), the startup time and the lookup throughput over all the
+# routes in a random order (`/r/*`, logged as `./bench.sh` does).
+
+if [ $# != 1 ]; then
+    echo 'usage: ./bench-routes.sh <framework>'
+    echo '       (set FEATURES to build the framework with more cargo features)'
+    exit 1
+fi
+framework="$1"
+
+read -r -a route_counts <<< "${ROUTE_COUNTS:-100 1000 10000}"
+features="${FEATURES:+$FEATURES }many-routes"
+
+export PATHS='/r/*'
+
+wd="$PWD"
+result=''
+for routes in "${route_counts[@]}"; do
+    echo "=== $routes routes ==="
+
+    cd ./$framework
+    # build the dependencies outside of the measurement
+    ROUTES=$routes cargo build --release --features "$features"
+    touch ./src/main.rs
+    started=$(date +%s%N)
+    ROUTES=$routes cargo build --release --features "$features"
+    build_ms=$(( ($(date +%s%N) - started) / 1000000 ))
+    cd $wd
+
+    ROUTES=$routes FEATURES="$features" ./bench.sh "$framework" "routes-$routes"
+
+    log=$(ls -t ./.log/$framework-*-routes-$routes.jsonc | head -n 1)
+    startup_ms=$(head -n 1 "$log" | sed 's/.*ready in \([0-9]*\) ms.*/\1/')
+    rps=$(tail -n +2 "$log" | jq '."/r/*"')
+
+    if [ "$result" != '' ]; then
+        result="$result,"
+    fi
+    result="$result{\"routes\": $routes, \"build_ms\": $build_ms, \"startup_ms\": $startup_ms, \"rps\": $rps}"
+done
+
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/$framework-$timestamp-routes.jsonc"
+echo "/* route table sizes${FEATURES:+ (features: $FEATURES)} */" >  $log_jsonc
+echo                                                               >> $log_jsonc
+echo "[$result]" | jq                                              >> $log_jsonc
+
+echo
+echo "Results:"
+tail -n +2 $log_jsonc | jq -c '.[]'
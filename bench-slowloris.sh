@@ -0,0 +1,91 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Check that a framework keeps its throughput while slow connections are
+# reaped: benchmarks PATHS ('/plaintext /db' by default) in three runs,
+#
+#   baseline      with the timeouts, without slow connections
+#   unprotected   without the timeouts, with SLOWLORIS (1000 by default) slow connections
+#   protected     with both
+#
+# where the timeouts are HEADER_READ_TIMEOUT_MS (1000 by default),
+# KEEP_ALIVE_TIMEOUT_MS (2000) and REQUEST_TIMEOUT_MS (2000) for axum, and
+# for ohkami, which has no separate header-read timeout, OHKAMI_KEEPALIVE_TIMEOUT
+# (the longer of the first two rounded up to seconds, as its `run.sh` sets it)
+# and REQUEST_TIMEOUT_MS, logging requests/sec and how many slow connections
+# were reaped per path. ohkami's request timeout only starts once the body is
+# read, so its `body` slow connections are reported apart, as unprotected.
+
+if [ $# != 1 ]; then
+    echo 'usage: ./bench-slowloris.sh <framework>'
+    echo '       (set FEATURES to build the framework with cargo features)'
+    exit 1
+fi
+framework="$1"
+
+sockets="${SLOWLORIS:-1000}"
+unset SLOWLORIS
+header_read_ms=${HEADER_READ_TIMEOUT_MS:-1000}
+keep_alive_ms=${KEEP_ALIVE_TIMEOUT_MS:-2000}
+unset HEADER_READ_TIMEOUT_MS KEEP_ALIVE_TIMEOUT_MS
+if [ "$framework" == 'ohkami' ]; then
+    timeout_ms=$(( header_read_ms > keep_alive_ms ? header_read_ms : keep_alive_ms ))
+    timeouts=(
+        OHKAMI_KEEPALIVE_TIMEOUT=${OHKAMI_KEEPALIVE_TIMEOUT:-$(( (timeout_ms + 999) / 1000 ))}
+        REQUEST_TIMEOUT_MS=${REQUEST_TIMEOUT_MS:-2000}
+    )
+    # slow connections reaped by the timeouts, and the others
+    protected_kinds='["head", "idle"]'
+    unprotected_kinds='["body"]'
+    timeouts_note=', `body` unprotected: the request timeout starts once the body is read'
+else
+    timeouts=(
+        HEADER_READ_TIMEOUT_MS=$header_read_ms
+        KEEP_ALIVE_TIMEOUT_MS=$keep_alive_ms
+        REQUEST_TIMEOUT_MS=${REQUEST_TIMEOUT_MS:-2000}
+    )
+    protected_kinds='["head", "body", "idle"]'
+    unprotected_kinds='[]'
+    timeouts_note=''
+fi
+unset OHKAMI_KEEPALIVE_TIMEOUT REQUEST_TIMEOUT_MS
+
+export PATHS="${PATHS:-/plaintext /db}"
+
+result=''
+for run in baseline unprotected protected; do
+    echo "=== $run ==="
+    case "$run" in
+        'baseline')    env "${timeouts[@]}" ./bench.sh "$framework" "slowloris-$run" ;;
+        'unprotected') env SLOWLORIS=$sockets ./bench.sh "$framework" "slowloris-$run" ;;
+        'protected')   env "${timeouts[@]}" SLOWLORIS=$sockets ./bench.sh "$framework" "slowloris-$run" ;;
+    esac
+
+    log=$(ls -t ./.log/$framework-*-slowloris-$run.jsonc | head -n 1)
+    if [ "$result" != '' ]; then
+        result="$result,"
+    fi
+    result="$result\"$run\": $(tail -n +2 "$log" | jq -c .)"
+done
+
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/$framework-$timestamp-slowloris.jsonc"
+echo "/* slowloris, $sockets sockets (${timeouts[*]}$timeouts_note)${FEATURES:+ (features: $FEATURES)} */" >  $log_jsonc
+echo                                                                                                       >> $log_jsonc
+echo "{$result}" | jq                                                                                      >> $log_jsonc
+
+echo
+echo "Results (requests/sec and slow connections reaped per path):"
+tail -n +2 $log_jsonc | jq -c --argjson kinds "$protected_kinds" --argjson others "$unprotected_kinds" '
+    . as $runs | .baseline | keys[] | . as $path | $runs.protected[$path].slowloris as $slow | {
+        path:        $path,
+        baseline:    $runs.baseline[$path],
+        unprotected: $runs.unprotected[$path].load,
+        protected:   $runs.protected[$path].load,
+        reaped:      [$kinds[] as $kind | $slow[$kind].reaped] | add,
+        still_open:  [$kinds[] as $kind | $slow[$kind].open] | add
+    } + if $others == [] then {} else {
+        unprotected_kinds: [$others[] as $kind | {($kind): {reaped: $slow[$kind].reaped, still_open: $slow[$kind].open}}] | add
+    } end
+'
@@ -0,0 +1,46 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Compare small-file and large-file throughput of a framework with the
+# `static-files` feature: requests/sec of `/static/<size>.bin` for each size
+# in STATIC_SIZES ('1k 1m' by default, any of `gen-static.sh`'s 1k 16k 256k
+# 1m), logged as `./bench.sh` does. Both servers read the (ranged) content
+# into memory per request: ohkami has no streaming file body, so neither
+# streams nor uses `sendfile`.
+
+if [ $# != 1 ]; then
+    echo 'usage: ./bench-static.sh <framework>'
+    echo '       (set FEATURES to build the framework with more cargo features)'
+    exit 1
+fi
+framework="$1"
+
+read -r -a static_sizes <<< "${STATIC_SIZES:-1k 1m}"
+features="${FEATURES:+$FEATURES }static-files"
+
+if [ ! -d ./static ]; then
+    ./gen-static.sh ./static
+fi
+
+result=''
+for size in "${static_sizes[@]}"; do
+    echo "=== $size files ==="
+    PATHS="/static/$size.bin" FEATURES="$features" ./bench.sh "$framework" "static-$size"
+
+    log=$(ls -t ./.log/$framework-*-static-$size.jsonc | head -n 1)
+    if [ "$result" != '' ]; then
+        result="$result,"
+    fi
+    result="$result{\"size\": \"$size\", \"rps\": $(tail -n +2 "$log" | jq ".\"/static/$size.bin\"")}"
+done
+
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/$framework-$timestamp-static.jsonc"
+echo "/* static file sizes, read into memory per request${FEATURES:+ (features: $FEATURES)} */" >  $log_jsonc
+echo                                                                                           >> $log_jsonc
+echo "[$result]" | jq                                                                          >> $log_jsonc
+
+echo
+echo "Results:"
+tail -n +2 $log_jsonc | jq -c '.[]'
@@ -0,0 +1,97 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Records the allocations per request of each endpoint, as reported by the
+# `alloc-count` feature, and fails if any of them allocates more than in
+# a previously recorded log.
+
+if [ $# != 1 ] && [ $# != 2 ]; then
+    echo 'usage: ./check-allocs.sh <framework> [<baseline .log/allocs-*.jsonc>]'
+    echo '       (set FEATURES to add cargo features to `alloc-count`)'
+    exit 1
+fi
+framework="$1"
+baseline="${2:-}"
+
+function cleanup() {
+    kill $(ps aux | awk '/target\/release/ {print $2}') || :
+    docker container stop 'postgres'
+}
+(cleanup 2>&1 | cat > /dev/null) || :
+
+docker run -d --rm \
+    -p 5432:5432 \
+    -e POSTGRES_USER=benchmarkdbuser \
+    -e POSTGRES_PASSWORD=benchmarkdbpass \
+    -e POSTGRES_DB=hello_world \
+    -v $PWD/postgres:/docker-entrypoint-initdb.d \
+    --name postgres \
+    postgres:17-bookworm
+
+sleep 5s
+
+FEATURES="alloc-count${FEATURES:+ $FEATURES}"
+export FEATURES
+
+wd="$PWD"
+cd ./$framework && \
+cargo build --release --features "$FEATURES" && \
+(./run.sh &) && \
+sleep 2 && \
+cd $wd
+
+paths=(
+    '/json'
+    '/db'
+    '/queries?q=20'
+    '/fortunes'
+    '/updates?q=20'
+    '/plaintext'
+)
+result=''
+for path in "${paths[@]}"; do
+    # the minimum of a few sequential requests, skipping lazy initializations
+    min_count=''
+    min_bytes=''
+    for _ in 1 2 3 4 5; do
+        headers=$(curl --silent --output /dev/null --dump-header - "http://localhost:8000$path")
+        count=$(echo "$headers" | awk 'tolower($1) == "x-alloc-count:" {print $2}' | tr -d '\r')
+        bytes=$(echo "$headers" | awk 'tolower($1) == "x-alloc-bytes:" {print $2}' | tr -d '\r')
+        if [ "$min_count" = '' ] || [ "$count" -lt "$min_count" ]; then
+            min_count=$count
+            min_bytes=$bytes
+        fi
+    done
+
+    echo "$path: $min_count allocations, $min_bytes bytes"
+    if [ "$result" != '' ]; then
+        result="$result,"
+    fi
+    result="$result\"$path\": {\"count\": $min_count, \"bytes\": $min_bytes}"
+done
+result="{$result}"
+
+(cleanup 2>&1 | cat > /dev/null) || :
+
+timestamp=$(date -u +'%Y%m%d%H%M%S')
+log_jsonc="./.log/allocs-$framework-$timestamp.jsonc"
+echo "/* allocations per request (features: $FEATURES) */" >  $log_jsonc
+echo                                                      >> $log_jsonc
+echo $result | jq                                         >> $log_jsonc
+
+if [ "$baseline" != '' ]; then
+    # strip the leading comment to read the baseline as JSON
+    regressions=$(jq --slurpfile base <(grep -v '^/\*' "$baseline") --argjson now "$result" -rn '
+        $now | to_entries[]
+        | select($base[0][.key] != null and .value.count > $base[0][.key].count)
+        | "\(.key): \($base[0][.key].count) -> \(.value.count) allocations"
+    ')
+    if [ "$regressions" != '' ]; then
+        echo
+        echo "allocation regressions against $baseline:"
+        echo "$regressions"
+        exit 1
+    fi
+fi
+echo "Done !"
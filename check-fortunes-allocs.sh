@@ -0,0 +1,87 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Checks that `/fortunes` borrows its messages from the rows (`FortuneRows`):
+# counts the allocations of real requests, as reported by the `alloc-count`
+# feature, with the fortunes of init.sql and then with EXTRA_FORTUNES (100
+# by default) more rows, and fails unless each extra row costs fewer than 2
+# allocations. Decoding a row allocates once (its column ranges); copying
+# its message into a `String`, as `/fortunes` did before, is a second.
+
+if [ $# != 0 ]; then
+    echo 'usage: ./check-fortunes-allocs.sh'
+    echo '       (set FEATURES to add cargo features to `alloc-count`)'
+    exit 1
+fi
+extra="${EXTRA_FORTUNES:-100}"
+
+function cleanup() {
+    kill $(ps aux | awk '/target\/release/ {print $2}') || :
+    docker container stop 'postgres'
+}
+(cleanup 2>&1 | cat > /dev/null) || :
+
+docker run -d --rm \
+    -p 5432:5432 \
+    -e POSTGRES_USER=benchmarkdbuser \
+    -e POSTGRES_PASSWORD=benchmarkdbpass \
+    -e POSTGRES_DB=hello_world \
+    -v $PWD/postgres:/docker-entrypoint-initdb.d \
+    --name postgres \
+    postgres:17-bookworm
+
+sleep 5s
+
+FEATURES="alloc-count${FEATURES:+ $FEATURES}"
+export FEATURES
+
+function psql() {
+    docker exec postgres psql --quiet -U benchmarkdbuser -d hello_world -c "$1"
+}
+
+# the minimum of a few sequential requests, skipping lazy initializations
+function fortunes_allocs() {
+    min_count=''
+    for _ in 1 2 3 4 5; do
+        count=$(curl --silent --output /dev/null --dump-header - 'http://localhost:8000/fortunes' \
+            | awk 'tolower($1) == "x-alloc-count:" {print $2}' | tr -d '\r')
+        if [ "$min_count" = '' ] || [ "$count" -lt "$min_count" ]; then
+            min_count=$count
+        fi
+    done
+    echo $min_count
+}
+
+failed=0
+wd="$PWD"
+for framework in ohkami axum; do
+    cd ./$framework && \
+    cargo build --release --features "$FEATURES" && \
+    (./run.sh &) && \
+    sleep 2 && \
+    cd $wd
+
+    base=$(fortunes_allocs)
+    psql "INSERT INTO Fortune (id, message)
+          SELECT 1000 + n, 'Extra fortune number ' || n || ', long enough to be allocated.'
+          FROM generate_series(1, $extra) AS n"
+    more=$(fortunes_allocs)
+    psql 'DELETE FROM Fortune WHERE id > 1000'
+
+    kill $(ps aux | awk '/target\/release/ {print $2}') || :
+    sleep 1
+
+    echo "$framework: $base allocations, $more with $extra more rows"
+    if [ $((more - base)) -ge $((2 * extra)) ]; then
+        echo "FAILED: $framework allocates $(((more - base) / extra)) times per extra fortune row"
+        failed=1
+    fi
+done
+
+(cleanup 2>&1 | cat > /dev/null) || :
+
+if [ $failed != 0 ]; then
+    exit 1
+fi
+echo "Done !"
@@ -0,0 +1,80 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Asserts that ohkami and axum render byte-identical `/fortunes` pages
+# for the `postgres/init.sql` data set.
+
+function fetch_fortunes () {
+    framework="$1"
+    output="$2"
+
+    wd="$PWD"
+
+    cd ./$framework && \
+    cargo build --release ${FEATURES:+--features "$FEATURES"} && \
+    (./run.sh &) && \
+    sleep 2 && \
+    cd $wd
+
+    curl --silent --fail 'http://localhost:8000/fortunes' >| "$output"
+
+    kill $(ps aux | awk '/target\/release/ {print $2}')
+    sleep 1
+}
+
+function cleanup() {
+    kill $(ps aux | awk '/target\/release/ {print $2}') || :
+    docker container stop 'postgres'
+}
+
+(cleanup 2>&1 | cat > /dev/null) || :
+
+docker run -d --rm \
+    -p 5432:5432 \
+    -e POSTGRES_USER=benchmarkdbuser \
+    -e POSTGRES_PASSWORD=benchmarkdbpass \
+    -e POSTGRES_DB=hello_world \
+    -v $PWD/postgres:/docker-entrypoint-initdb.d \
+    --name postgres \
+    postgres:17-bookworm
+
+sleep 5s
+
+tmp="$(mktemp -d)"
+fetch_fortunes ohkami "$tmp/ohkami.html"
+fetch_fortunes axum   "$tmp/axum.html"
+
+(cleanup 2>&1 | cat > /dev/null) || :
+
+failed=0
+function expect () {
+    description="$1"
+    shift
+    if "$@" > /dev/null; then
+        echo "ok:     $description"
+    else
+        echo "FAILED: $description"
+        failed=1
+    fi
+}
+
+expect 'ohkami and axum are byte-identical' \
+    cmp "$tmp/ohkami.html" "$tmp/axum.html"
+expect 'starts with the doctype' \
+    grep --quiet '^<!DOCTYPE html>' "$tmp/ohkami.html"
+expect 'contains all 13 fortunes' \
+    test "$(grep --only-matching '<tr><td>' "$tmp/ohkami.html" | wc -l)" -eq 13
+expect 'escapes the <script> row' \
+    grep --quiet --fixed-strings '<tr><td>11</td><td>&lt;script&gt;alert(&quot;This should not be displayed in a browser alert box.&quot;);' "$tmp/ohkami.html"
+expect 'does not contain a raw <script>' \
+    bash -c "! grep --quiet --fixed-strings '<script>' '$tmp/ohkami.html'"
+expect 'keeps the Japanese UTF-8 row' \
+    grep --quiet --fixed-strings '<tr><td>12</td><td>フレームワークのベンチマーク</td></tr>' "$tmp/ohkami.html"
+
+if [ $failed != 0 ]; then
+    echo
+    diff "$tmp/ohkami.html" "$tmp/axum.html" || :
+    exit 1
+fi
+echo "Done !"
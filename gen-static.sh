@@ -0,0 +1,17 @@
+#!/bin/bash
+
+set -Cue -o pipefail
+
+# Generates the files served at `/static/:name` by the `static-files`
+# feature into ./static (STATIC_DIR of both servers), from 1 KiB to 1 MiB.
+
+dir="${1:-./static}"
+
+mkdir -p "$dir"
+for size in 1k 16k 256k 1m; do
+    bytes=$(numfmt --from=iec "${size^^}")
+    head -c "$bytes" /dev/urandom >| "$dir/$size.bin"
+done
+echo '<!DOCTYPE html><html><head><title>static</title></head><body>Hello, World!</body></html>' >| "$dir/index.html"
+
+ls -l "$dir"
@@ -0,0 +1,76 @@
+//! Load generator for the scenarios `wrk` can't drive, printing a JSON
+//! summary to stdout for `bench.sh` to log:
+//!
+//! ```sh
+//! loadgen ws-echo      <url> <sockets> <seconds>
+//! loadgen ws-broadcast <url> <sockets> <seconds>
+//! loadgen sse          <url> <streams> <seconds>
+//! loadgen slowloris    <url> <sockets> <seconds>
+//! ```
+
+mod slowloris;
+mod sse;
+mod ws;
+
+use std::time::Duration;
+
+fn usage() -> ! {
+    eprintln!("usage: loadgen (ws-echo | ws-broadcast | sse | slowloris) <url> <sockets or streams> <seconds>");
+    std::process::exit(1)
+}
+
+#[tokio::main]
+async fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let [mode, url, sockets, seconds] = &args[..] else {
+        usage()
+    };
+
+    let sockets: usize = sockets.parse().unwrap_or_else(|_| usage());
+    let duration = Duration::from_secs(seconds.parse().unwrap_or_else(|_| usage()));
+
+    let summary = match mode.as_str() {
+        "ws-echo" => ws::echo(url, sockets, duration).await.to_string(),
+        "ws-broadcast" => ws::broadcast(url, sockets, duration).await.to_string(),
+        "sse" => sse::sse(url, sockets, duration).await.to_string(),
+        "slowloris" => slowloris::slowloris(url, sockets, duration)
+            .await
+            .to_string(),
+        _ => usage(),
+    };
+    println!("{summary}");
+}
+
+/// Messages (or events)/sec and latency percentiles of a run.
+pub struct Summary {
+    pub mode: &'static str,
+    /// sockets or streams
+    pub sockets: usize,
+    pub duration: Duration,
+    /// in microseconds, one per message
+    pub latencies: Vec<u32>,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut latencies = self.latencies.clone();
+        latencies.sort_unstable();
+        let percentile = |p: usize| match latencies.len() {
+            0 => 0,
+            n => latencies[(n - 1) * p / 100],
+        };
+
+        write!(
+            f,
+            r#"{{"mode": "{}", "sockets": {}, "seconds": {}, "msgs_per_sec": {:.1}, "latency_us": {{"p50": {}, "p90": {}, "p99": {}, "max": {}}}}}"#,
+            self.mode,
+            self.sockets,
+            self.duration.as_secs(),
+            latencies.len() as f64 / self.duration.as_secs_f64(),
+            percentile(50),
+            percentile(90),
+            percentile(99),
+            percentile(100),
+        )
+    }
+}
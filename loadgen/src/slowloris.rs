@@ -0,0 +1,190 @@
+//! Slowloris scenario: `sockets` connections that never complete a request,
+//! spread over three kinds:
+//!
+//! * `head`: sends the head of a `GET /plaintext` one header line at a time.
+//! * `body`: sends the head of a `POST /worlds/batch` announcing 1 MiB, then
+//!   its body one byte at a time.
+//! * `idle`: sends a whole `GET /plaintext`, then nothing.
+//!
+//! Every `SLOWLORIS_INTERVAL_MS` (1000 by default), `head` and `body` send
+//! some more. A connection is reaped when the server closes it, or answers
+//! it (with a `408` or `431`, say) before its request is complete. Then it
+//! reconnects, until the duration is over.
+
+use std::time::{Duration, Instant};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Head,
+    Body,
+    Idle,
+}
+
+impl Kind {
+    const ALL: [Self; 3] = [Self::Head, Self::Body, Self::Idle];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Head => "head",
+            Self::Body => "body",
+            Self::Idle => "idle",
+        }
+    }
+
+    fn start(self, host: &str) -> String {
+        match self {
+            Self::Head => format!("GET /plaintext HTTP/1.1\r\nHost: {host}\r\n"),
+            Self::Body => format!(
+                "POST /worlds/batch HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: 1048576\r\n\r\n["
+            ),
+            Self::Idle => format!("GET /plaintext HTTP/1.1\r\nHost: {host}\r\n\r\n"),
+        }
+    }
+
+    /// What's sent every interval, if anything.
+    fn trickle(self) -> Option<&'static [u8]> {
+        match self {
+            Self::Head => Some(b"X-Slowloris: 1\r\n"),
+            Self::Body => Some(b" "),
+            Self::Idle => None,
+        }
+    }
+}
+
+/// How a slow connection ended.
+enum Held {
+    /// by the server, after this long
+    Reaped(Duration),
+    /// still open when the duration was over
+    Open,
+    Refused,
+}
+
+async fn hold(host: &str, kind: Kind, interval: Duration, deadline: Instant) -> Held {
+    let connected = Instant::now();
+    let Ok(mut stream) = TcpStream::connect(host).await else {
+        return Held::Refused;
+    };
+    if stream.write_all(kind.start(host).as_bytes()).await.is_err() {
+        return Held::Reaped(connected.elapsed());
+    }
+
+    let mut ticks = tokio::time::interval_at((connected + interval).into(), interval);
+    let mut buf = [0; 1024];
+    loop {
+        tokio::select! {
+            read = stream.read(&mut buf) => match (read, kind) {
+                // the response to the request of `idle`
+                (Ok(1..), Kind::Idle) => continue,
+                _ => return Held::Reaped(connected.elapsed()),
+            },
+            _ = ticks.tick(), if kind.trickle().is_some() => {
+                if stream.write_all(kind.trickle().unwrap()).await.is_err() {
+                    return Held::Reaped(connected.elapsed());
+                }
+            }
+            () = tokio::time::sleep_until(deadline.into()) => return Held::Open,
+        }
+    }
+}
+
+pub async fn slowloris(url: &str, sockets: usize, duration: Duration) -> Report {
+    let interval = Duration::from_millis(
+        std::env::var("SLOWLORIS_INTERVAL_MS")
+            .map(|n| n.parse().expect("invalid SLOWLORIS_INTERVAL_MS"))
+            .unwrap_or(1000),
+    );
+    let host = url
+        .strip_prefix("http://")
+        .expect("slowloris only supports `http://` urls")
+        .split('/')
+        .next()
+        .unwrap()
+        .to_string();
+    let deadline = Instant::now() + duration;
+
+    let mut tasks = Vec::with_capacity(sockets);
+    for i in 0..sockets {
+        let (host, kind) = (host.clone(), Kind::ALL[i % Kind::ALL.len()]);
+        tasks.push(tokio::spawn(async move {
+            let mut held = Vec::new();
+            while Instant::now() < deadline {
+                let end = hold(&host, kind, interval, deadline).await;
+                if let Held::Refused = end {
+                    tokio::time::sleep(interval).await;
+                }
+                held.push(end);
+            }
+            (kind, held)
+        }));
+    }
+
+    let mut report = Report {
+        sockets,
+        duration,
+        interval,
+        reaped_ms: [(); 3].map(|()| Vec::new()),
+        open: [0; 3],
+        refused: 0,
+    };
+    for task in tasks {
+        let (kind, held) = task.await.unwrap();
+        for end in held {
+            match end {
+                Held::Reaped(after) => {
+                    report.reaped_ms[kind as usize].push(after.as_millis() as u32)
+                }
+                Held::Open => report.open[kind as usize] += 1,
+                Held::Refused => report.refused += 1,
+            }
+        }
+    }
+    report
+}
+
+/// How many slow connections of each kind were reaped and after how long,
+/// and how many were still open at the end.
+pub struct Report {
+    sockets: usize,
+    duration: Duration,
+    interval: Duration,
+    /// by `Kind`
+    reaped_ms: [Vec<u32>; 3],
+    open: [usize; 3],
+    refused: usize,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"{{"mode": "slowloris", "sockets": {}, "seconds": {}, "interval_ms": {}"#,
+            self.sockets,
+            self.duration.as_secs(),
+            self.interval.as_millis(),
+        )?;
+        for kind in Kind::ALL {
+            let mut reaped_ms = self.reaped_ms[kind as usize].clone();
+            reaped_ms.sort_unstable();
+            let percentile = |p: usize| match reaped_ms.len() {
+                0 => 0,
+                n => reaped_ms[(n - 1) * p / 100],
+            };
+            write!(
+                f,
+                r#", "{}": {{"reaped": {}, "reaped_after_ms": {{"p50": {}, "max": {}}}, "open": {}}}"#,
+                kind.name(),
+                reaped_ms.len(),
+                percentile(50),
+                percentile(100),
+                self.open[kind as usize],
+            )?;
+        }
+        write!(f, r#", "refused": {}}}"#, self.refused)
+    }
+}
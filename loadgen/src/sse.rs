@@ -0,0 +1,64 @@
+//! SSE scenario: `streams` concurrent requests to an SSE endpoint, each
+//! requesting again when its stream ends, until the duration is over. The
+//! latency of each event is the time since the previous one on its stream
+//! (or since the request for the first one), so that it shows how far the
+//! server falls behind the requested interval as the fan-out grows.
+
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+
+use crate::Summary;
+
+pub async fn sse(url: &str, streams: usize, duration: Duration) -> Summary {
+    let client = reqwest::Client::builder()
+        .pool_max_idle_per_host(streams)
+        .build()
+        .unwrap();
+    let deadline = Instant::now() + duration;
+
+    let mut tasks = Vec::with_capacity(streams);
+    for _ in 0..streams {
+        let (client, url) = (client.clone(), url.to_string());
+        tasks.push(tokio::spawn(async move {
+            let mut latencies = Vec::new();
+            while Instant::now() < deadline {
+                let mut last = Instant::now();
+                let response = client
+                    .get(&url)
+                    .header("Accept", "text/event-stream")
+                    .send()
+                    .await
+                    .expect("could not request");
+                assert!(response.status().is_success(), "{}", response.status());
+
+                // Events are separated by a blank line, which may be split across chunks.
+                let mut body = response.bytes_stream();
+                let mut pending = Vec::new();
+                while let Ok(Some(chunk)) =
+                    tokio::time::timeout_at(deadline.into(), body.next()).await
+                {
+                    pending.extend_from_slice(&chunk.expect("could not read the stream"));
+                    while let Some(end) = pending.windows(2).position(|w| w == b"\n\n") {
+                        pending.drain(..end + 2);
+                        latencies.push(last.elapsed().as_micros() as u32);
+                        last = Instant::now();
+                    }
+                }
+            }
+            latencies
+        }));
+    }
+
+    let mut latencies = Vec::new();
+    for task in tasks {
+        latencies.extend(task.await.unwrap());
+    }
+
+    Summary {
+        mode: "sse",
+        sockets: streams,
+        duration,
+        latencies,
+    }
+}
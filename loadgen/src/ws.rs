@@ -0,0 +1,145 @@
+//! WebSocket scenarios:
+//!
+//! * `echo`: every socket sends a message and waits for it to come back, in a
+//!   loop. The latency is the round trip.
+//! * `broadcast`: every socket subscribes to `/ws/broadcast`, and one more
+//!   publishes a timestamped message every `WS_PUBLISH_INTERVAL_US` (1000 by
+//!   default). The latency is from publishing to each delivery.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::{net::TcpStream, sync::Barrier};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::Summary;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn connect(url: &str) -> Socket {
+    let (socket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .unwrap_or_else(|e| panic!("could not connect to {url}: {e}"));
+    socket
+}
+
+/// Connect `sockets` sockets, then run `f` on each of them at once when
+/// `barrier` (sized for them and any other waiters) is released.
+async fn run_all<F, Fut>(url: &str, sockets: usize, barrier: Arc<Barrier>, f: F) -> Vec<u32>
+where
+    F: Fn(Socket) -> Fut,
+    Fut: std::future::Future<Output = Vec<u32>> + Send + 'static,
+{
+    let mut tasks = Vec::with_capacity(sockets);
+    for _ in 0..sockets {
+        let run = f(connect(url).await);
+        let barrier = barrier.clone();
+        tasks.push(tokio::spawn(async move {
+            barrier.wait().await;
+            run.await
+        }));
+    }
+
+    let mut latencies = Vec::new();
+    for task in tasks {
+        latencies.extend(task.await.unwrap());
+    }
+    latencies
+}
+
+pub async fn echo(url: &str, sockets: usize, duration: Duration) -> Summary {
+    let payload = "Hello, World!";
+
+    let barrier = Arc::new(Barrier::new(sockets));
+    let latencies = run_all(url, sockets, barrier, |mut socket| async move {
+        let mut latencies = Vec::new();
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            let sent = Instant::now();
+            socket
+                .send(Message::text(payload))
+                .await
+                .expect("could not send");
+            loop {
+                match socket.next().await {
+                    Some(Ok(Message::Text(_) | Message::Binary(_))) => break,
+                    Some(Ok(_)) => continue,
+                    _ => panic!("socket closed by the server"),
+                }
+            }
+            latencies.push(sent.elapsed().as_micros() as u32);
+        }
+        let _ = socket.close(None).await;
+        latencies
+    })
+    .await;
+
+    Summary {
+        mode: "ws-echo",
+        sockets,
+        duration,
+        latencies,
+    }
+}
+
+pub async fn broadcast(url: &str, sockets: usize, duration: Duration) -> Summary {
+    let interval = Duration::from_micros(
+        std::env::var("WS_PUBLISH_INTERVAL_US")
+            .map(|n| n.parse().expect("invalid WS_PUBLISH_INTERVAL_US"))
+            .unwrap_or(1000),
+    );
+    let epoch = Instant::now();
+    let barrier = Arc::new(Barrier::new(sockets + 1));
+
+    let (mut publisher_sink, mut publisher_stream) = connect(url).await.split();
+    let publish = {
+        let barrier = barrier.clone();
+        async move {
+            // Start with the subscribers, once all of them are connected.
+            barrier.wait().await;
+
+            let mut ticks = tokio::time::interval(interval);
+            let deadline = Instant::now() + duration;
+            while Instant::now() < deadline {
+                ticks.tick().await;
+                let timestamp = epoch.elapsed().as_micros().to_string();
+                publisher_sink
+                    .send(Message::text(timestamp))
+                    .await
+                    .expect("could not publish");
+            }
+            let _ = publisher_sink.close().await;
+        }
+    };
+    let publisher = tokio::spawn(publish);
+    // The publisher is subscribed too; keep it from lagging.
+    tokio::spawn(async move { while let Some(Ok(_)) = publisher_stream.next().await {} });
+
+    let latencies = run_all(url, sockets, barrier, |mut socket| async move {
+        let mut latencies = Vec::new();
+        let deadline = Instant::now() + duration;
+        while let Ok(Some(Ok(message))) =
+            tokio::time::timeout_at(deadline.into(), socket.next()).await
+        {
+            if let Message::Text(timestamp) = message {
+                let published: u128 = timestamp.parse().expect("not a timestamp");
+                latencies.push((epoch.elapsed().as_micros() - published) as u32);
+            }
+        }
+        let _ = socket.close(None).await;
+        latencies
+    })
+    .await;
+
+    publisher.await.unwrap();
+
+    Summary {
+        mode: "ws-broadcast",
+        sockets,
+        duration,
+        latencies,
+    }
+}
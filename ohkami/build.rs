@@ -0,0 +1,62 @@
+//! Generates the route table of the `many-routes` feature into
+//! `$OUT_DIR/many_routes.rs`, as ohkami's routes are a (nested) tuple
+//! known at compile time. `ROUTES` (1000 by default) is read at build time.
+//!
+//! Route `i` is the `i % 3`th of these shapes, 8 of each per group
+//! `g = i / 24` and leaf `l = i % 24 / 3`:
+//!
+//! * `/r/g{g}/s{l}`
+//! * `/r/g{g}/s{l}/:id`
+//! * `/r/g{g}/s{l}/:id/items`
+//!
+//! `axum/src/common/many_routes.rs` and `wrk/routes.lua` follow the same shapes.
+
+use std::{env, fs, path::Path};
+
+/// Most items per `Ohkami::new` tuple. Larger tables are split
+/// into `"/".By(...)` children, which ohkami merges into one router.
+const FANOUT: usize = 8;
+
+fn route(i: usize) -> String {
+    let (g, l) = (i / 24, i % 24 / 3);
+    match i % 3 {
+        0 => format!(r#""/r/g{g}/s{l}".GET(static_route)"#),
+        1 => format!(r#""/r/g{g}/s{l}/:id".GET(param_route)"#),
+        _ => format!(r#""/r/g{g}/s{l}/:id/items".GET(param_route)"#),
+    }
+}
+
+fn ohkami(items: Vec<String>) -> String {
+    if items.len() <= FANOUT {
+        return format!("Ohkami::new(({},))", items.join(", "));
+    }
+    let children = items
+        .chunks(FANOUT)
+        .map(|chunk| format!(r#""/".By({})"#, ohkami(chunk.to_vec())))
+        .collect();
+    ohkami(children)
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=ROUTES");
+
+    if env::var_os("CARGO_FEATURE_MANY_ROUTES").is_none() {
+        return;
+    }
+
+    let routes: usize = env::var("ROUTES")
+        .map(|n| n.parse().expect("invalid ROUTES"))
+        .unwrap_or(1000);
+    assert!(routes > 0, "ROUTES must be positive");
+
+    let generated = format!(
+        "/// The {routes} generated routes.\npub fn ohkami() -> Ohkami {{\n    {}\n}}\n",
+        ohkami((0..routes).map(route).collect()),
+    );
+    fs::write(
+        Path::new(&env::var("OUT_DIR").unwrap()).join("many_routes.rs"),
+        generated,
+    )
+    .unwrap();
+}
@@ -0,0 +1,92 @@
+//! The global allocator, selected by cargo features: `jemalloc` and `system-alloc`
+//! take precedence over `mimalloc` (default), so that they can be selected
+//! without `--no-default-features`. The system allocator is used if none.
+//!
+//! With `alloc-count`, it's wrapped to count the allocations made while
+//! serving each request (see `fangs::CountAllocs`).
+
+#[cfg(all(feature = "jemalloc", feature = "system-alloc"))]
+compile_error!("select at most one allocator of `jemalloc` and `system-alloc`");
+
+#[cfg(all(feature = "mimalloc", not(any(feature = "jemalloc", feature = "system-alloc"))))]
+use mimalloc::MiMalloc as Allocator;
+#[cfg(all(feature = "mimalloc", not(any(feature = "jemalloc", feature = "system-alloc"))))]
+const ALLOCATOR: Allocator = mimalloc::MiMalloc;
+
+#[cfg(feature = "jemalloc")]
+use tikv_jemallocator::Jemalloc as Allocator;
+#[cfg(feature = "jemalloc")]
+const ALLOCATOR: Allocator = tikv_jemallocator::Jemalloc;
+
+#[cfg(any(feature = "system-alloc", not(any(feature = "mimalloc", feature = "jemalloc"))))]
+use std::alloc::System as Allocator;
+#[cfg(any(feature = "system-alloc", not(any(feature = "mimalloc", feature = "jemalloc"))))]
+const ALLOCATOR: Allocator = std::alloc::System;
+
+#[cfg(not(feature = "alloc-count"))]
+#[global_allocator]
+static GLOBAL: Allocator = ALLOCATOR;
+
+#[cfg(feature = "alloc-count")]
+#[global_allocator]
+static GLOBAL: counting::Counting<Allocator> = counting::Counting(ALLOCATOR);
+
+#[cfg(feature = "alloc-count")]
+pub mod counting {
+    use std::alloc::{GlobalAlloc, Layout};
+    use std::cell::Cell;
+
+    tokio::task_local! {
+        /// allocations of the task currently being polled
+        static ALLOCS: Allocs;
+    }
+
+    #[derive(Default)]
+    struct Allocs {
+        count: Cell<u64>,
+        bytes: Cell<u64>,
+    }
+
+    pub struct Counting<A>(pub A);
+
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for Counting<A> {
+        #[inline]
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            unsafe {self.0.alloc(layout)}
+        }
+        #[inline]
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            unsafe {self.0.alloc_zeroed(layout)}
+        }
+        #[inline]
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record(new_size);
+            unsafe {self.0.realloc(ptr, layout, new_size)}
+        }
+        #[inline]
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe {self.0.dealloc(ptr, layout)}
+        }
+    }
+
+    /// Never allocates by itself: `ALLOCS` is const-initialized and has no destructor.
+    #[inline]
+    fn record(size: usize) {
+        let _ = ALLOCS.try_with(|allocs| {
+            allocs.count.set(allocs.count.get() + 1);
+            allocs.bytes.set(allocs.bytes.get() + size as u64);
+        });
+    }
+
+    /// Runs `task` counting its allocations, and returns its output
+    /// with `(number of allocations, allocated bytes)`.
+    pub async fn count<T>(task: impl Future<Output = T>) -> (T, (u64, u64)) {
+        ALLOCS.scope(Allocs::default(), async {
+            let output = task.await;
+            let counts = ALLOCS.with(|allocs| (allocs.count.get(), allocs.bytes.get()));
+            (output, counts)
+        }).await
+    }
+}
@@ -0,0 +1,135 @@
+//! `GET`/`POST /api/worlds` and `GET`/`PUT`/`PATCH`/`DELETE /api/worlds/:id`,
+//! a CRUD service on the `ApiWorld` table (see `postgres::crud`).
+//!
+//! Invalid input is answered by 422 with the reason as text, unknown ids by
+//! 404, and taken ids by 409. Bodies are (de)serialized by ohkami's `JSON`
+//! (serde_json) whichever serializer is selected.
+
+use ohkami::prelude::*;
+use ohkami::format::{JSON, Query};
+use crate::{
+    models::{WorldInput, WorldsPage, WorldsPageMeta},
+    postgres::Postgres,
+};
+
+pub fn ohkami() -> Ohkami {
+    Ohkami::new((
+        "/"   .GET(list).POST(create),
+        "/:id".GET(read).PUT(replace).PATCH(modify).DELETE(delete),
+    ))
+}
+
+fn not_found(id: impl std::fmt::Display) -> Response {
+    Response::NotFound().with_text(format!("world {id} not found"))
+}
+
+async fn list(
+    Query(q): Query<WorldsPageMeta<'_>>,
+    Context(db): Context<'_, Postgres>,
+) -> Response {
+    let (after, limit, offset) = match q.parse() {
+        Ok(page)     => page,
+        Err(message) => return Response::UnprocessableEntity().with_text(message),
+    };
+
+    let worlds = db.list_api_worlds(after, limit, offset).await;
+    let next_after = match worlds.last() {
+        Some(last) if worlds.len() as i64 == limit => Some(last.id),
+        _                                          => None,
+    };
+
+    Response::OK().with_json(WorldsPage { worlds, next_after })
+}
+
+async fn create(
+    Context(db): Context<'_, Postgres>,
+    JSON(input): JSON<WorldInput>,
+) -> Response {
+    let (id, randomnumber) = match input.validate_new() {
+        Ok(new)      => new,
+        Err(message) => return Response::UnprocessableEntity().with_text(message),
+    };
+
+    match db.insert_api_world(id, randomnumber).await {
+        Some(world) => {
+            let mut res = Response::Created();
+            res.headers.set().Location(format!("/api/worlds/{}", world.id));
+            res.with_json(world)
+        }
+        None => Response::Conflict().with_text(match id {
+            Some(id) => format!("world {id} already exists"),
+            None     => String::from("the generated id was already taken, retry"),
+        }),
+    }
+}
+
+async fn read(
+    id: u32,
+    Context(db): Context<'_, Postgres>,
+) -> Response {
+    /* ids beyond `i32` can't exist */
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id)
+    };
+
+    match db.select_api_world(id).await {
+        Some(world) => Response::OK().with_json(world),
+        None        => not_found(id),
+    }
+}
+
+async fn replace(
+    id: u32,
+    Context(db): Context<'_, Postgres>,
+    JSON(input): JSON<WorldInput>,
+) -> Response {
+    /* ids beyond `i32` can't exist */
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id)
+    };
+    let randomnumber = match input.validate_put(id) {
+        Ok(randomnumber) => randomnumber,
+        Err(message)     => return Response::UnprocessableEntity().with_text(message),
+    };
+
+    match db.update_api_world(id, Some(randomnumber)).await {
+        Some(world) => Response::OK().with_json(world),
+        None        => not_found(id),
+    }
+}
+
+async fn modify(
+    id: u32,
+    Context(db): Context<'_, Postgres>,
+    JSON(input): JSON<WorldInput>,
+) -> Response {
+    /* ids beyond `i32` can't exist */
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id)
+    };
+    let randomnumber = match input.validate_patch(id) {
+        Ok(randomnumber) => randomnumber,
+        Err(message)     => return Response::UnprocessableEntity().with_text(message),
+    };
+
+    match db.update_api_world(id, randomnumber).await {
+        Some(world) => Response::OK().with_json(world),
+        None        => not_found(id),
+    }
+}
+
+async fn delete(
+    id: u32,
+    Context(db): Context<'_, Postgres>,
+) -> Response {
+    /* ids beyond `i32` can't exist */
+    let Ok(id) = i32::try_from(id) else {
+        return not_found(id)
+    };
+
+    if db.delete_api_world(id).await {
+        Response::NoContent()
+    } else {
+        not_found(id)
+    }
+}
@@ -8,3 +8,540 @@ impl FangAction for SetServer {
         res.headers.set().Server("ohkami");
     }
 }
+
+/// Answers `503 Service Unavailable` with `Retry-After: RETRY_AFTER_SECS`
+/// (1 by default) past `MAX_IN_FLIGHT` requests being handled by a runtime.
+pub use limit_in_flight::LimitInFlight;
+mod limit_in_flight {
+    use ohkami::{Fang, FangProc, Request, Response};
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+
+    #[derive(Clone)]
+    pub struct LimitInFlight {
+        max:         usize,
+        retry_after: String,
+        in_flight:   Arc<AtomicUsize>,
+    }
+
+    impl LimitInFlight {
+        /// `None` if `MAX_IN_FLIGHT` is not set.
+        pub fn from_env() -> Option<Self> {
+            let max: usize = std::env::var("MAX_IN_FLIGHT")
+                .map(|n| n.parse().expect("invalid MAX_IN_FLIGHT"))
+                .unwrap_or(0);
+            let retry_after: u32 = std::env::var("RETRY_AFTER_SECS")
+                .map(|n| n.parse().expect("invalid RETRY_AFTER_SECS"))
+                .unwrap_or(1);
+            (max > 0).then(|| Self { max, retry_after: retry_after.to_string(), in_flight: Arc::new(AtomicUsize::new(0)) })
+        }
+    }
+
+    impl<I: FangProc> Fang<I> for LimitInFlight {
+        type Proc = LimitInFlightProc<I>;
+        fn chain(&self, inner: I) -> Self::Proc {
+            LimitInFlightProc { limit: self.clone(), inner }
+        }
+    }
+
+    pub struct LimitInFlightProc<I> {
+        limit: LimitInFlight,
+        inner: I,
+    }
+
+    /// Counts a request as in flight until dropped, even if it's cancelled.
+    struct InFlight<'a>(&'a AtomicUsize);
+    impl Drop for InFlight<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    impl<I: FangProc> FangProc for LimitInFlightProc<I> {
+        async fn bite<'b>(&'b self, req: &'b mut Request) -> Response {
+            let in_flight = InFlight(&self.limit.in_flight);
+            if in_flight.0.fetch_add(1, Ordering::Relaxed) >= self.limit.max {
+                /* outside of `SetServer`, so set `Server` here too */
+                let mut res = Response::ServiceUnavailable();
+                res.headers.set()
+                    .Server("ohkami")
+                    .RetryAfter(self.limit.retry_after.clone());
+                return res
+            }
+
+            self.inner.bite(req).await
+        }
+    }
+}
+
+/// Answers `408 Request Timeout` to requests not handled in `REQUEST_TIMEOUT_MS`.
+/// Ohkami reads whole requests before they get there, so it only bounds
+/// handling them.
+pub use request_timeout::RequestTimeout;
+mod request_timeout {
+    use ohkami::{Fang, FangProc, Request, Response};
+    use std::time::Duration;
+
+    /// Answers 408 to requests not handled within `REQUEST_TIMEOUT_MS`. Fangs
+    /// only run once the request is read, so this doesn't cover a slow body.
+    #[derive(Clone)]
+    pub struct RequestTimeout {
+        timeout: Duration,
+    }
+
+    impl RequestTimeout {
+        /// `None` if `REQUEST_TIMEOUT_MS` is not set.
+        pub fn from_env() -> Option<Self> {
+            let timeout_ms: u64 = std::env::var("REQUEST_TIMEOUT_MS")
+                .map(|n| n.parse().expect("invalid REQUEST_TIMEOUT_MS"))
+                .unwrap_or(0);
+            (timeout_ms > 0).then(|| Self { timeout: Duration::from_millis(timeout_ms) })
+        }
+    }
+
+    impl<I: FangProc> Fang<I> for RequestTimeout {
+        type Proc = RequestTimeoutProc<I>;
+        fn chain(&self, inner: I) -> Self::Proc {
+            RequestTimeoutProc { timeout: self.timeout, inner }
+        }
+    }
+
+    pub struct RequestTimeoutProc<I> {
+        timeout: Duration,
+        inner:   I,
+    }
+
+    impl<I: FangProc> FangProc for RequestTimeoutProc<I> {
+        async fn bite<'b>(&'b self, req: &'b mut Request) -> Response {
+            match tokio::time::timeout(self.timeout, self.inner.bite(req)).await {
+                Ok(res) => res,
+                Err(_)  => {
+                    /* outside of `SetServer`, so set `Server` here too */
+                    let mut res = Response::RequestTimeout();
+                    res.headers.set().Server("ohkami");
+                    res
+                }
+            }
+        }
+    }
+}
+
+/// One `request` span per request, recording the path, the status and
+/// the latency. Fangs run before routing, so unlike axum's `route` (the
+/// matched pattern, like `/worlds/:id`), `path` is the raw request path.
+/// Postgres statements are traced as its child spans (see `crate::postgres`).
+#[cfg(feature = "tracing")]
+pub use trace::Trace;
+#[cfg(feature = "tracing")]
+mod trace {
+    use ohkami::{Fang, FangProc, Request, Response};
+    use tracing::{Instrument, field::Empty};
+
+    #[derive(Clone)]
+    pub struct Trace;
+
+    impl<I: FangProc> Fang<I> for Trace {
+        type Proc = TraceProc<I>;
+        fn chain(&self, inner: I) -> Self::Proc {
+            TraceProc { inner }
+        }
+    }
+
+    pub struct TraceProc<I> {
+        inner: I,
+    }
+
+    impl<I: FangProc> FangProc for TraceProc<I> {
+        async fn bite<'b>(&'b self, req: &'b mut Request) -> Response {
+            let span = tracing::info_span!("request",
+                method     = req.method.as_str(),
+                path       = req.path.str(),
+                status     = Empty,
+                latency_us = Empty,
+            );
+
+            let start = std::time::Instant::now();
+            let res = self.inner.bite(req).instrument(span.clone()).await;
+
+            span.record("status", res.status.code());
+            span.record("latency_us", start.elapsed().as_micros() as u64);
+            tracing::info!(parent: &span, "response");
+
+            res
+        }
+    }
+}
+
+/// Access log written by a batching task per runtime, so that requests
+/// never wait for the writes. Configured by environment variables:
+///
+/// - `ACCESS_LOG_FORMAT`: `common` (default), `combined` or `json`
+/// - `ACCESS_LOG_PATH`: file to append to (stdout by default)
+/// - `ACCESS_LOG_SAMPLE`: log 1 request out of N (1 by default)
+#[cfg(feature = "access-log")]
+pub use access_log::AccessLog;
+#[cfg(feature = "access-log")]
+mod access_log {
+    use ohkami::{Fang, FangProc, Request, Response};
+    use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+    use std::time::{Instant, SystemTime, UNIX_EPOCH};
+    use std::{fmt::Write, num::NonZeroU64};
+    use tokio::{io::AsyncWriteExt, sync::mpsc};
+
+    const CHANNEL_CAPACITY: usize = 1 << 16;
+    const BATCH_SIZE:       usize = 1 << 10;
+
+    #[derive(Clone, Copy)]
+    enum Format { Common, Combined, JSON }
+
+    #[derive(Clone)]
+    pub struct AccessLog {
+        format:  Format,
+        sample:  NonZeroU64,
+        counter: Arc<AtomicU64>,
+        lines:   mpsc::Sender<String>,
+    }
+
+    impl AccessLog {
+        /// Spawns the writer task onto the current runtime.
+        pub fn new() -> Self {
+            let format = match std::env::var("ACCESS_LOG_FORMAT").as_deref() {
+                Err(_) | Ok("common") => Format::Common,
+                Ok("combined")        => Format::Combined,
+                Ok("json")            => Format::JSON,
+                Ok(other) => panic!("unknown ACCESS_LOG_FORMAT: `{other}`"),
+            };
+            let sample = std::env::var("ACCESS_LOG_SAMPLE")
+                .map(|n| n.parse().expect("invalid ACCESS_LOG_SAMPLE"))
+                .unwrap_or(1);
+            let sample = NonZeroU64::new(sample).expect("ACCESS_LOG_SAMPLE must be at least 1");
+            let path = std::env::var("ACCESS_LOG_PATH").ok();
+
+            let (lines, mut rx) = mpsc::channel::<String>(CHANNEL_CAPACITY);
+            tokio::spawn(async move {
+                let mut out: std::pin::Pin<Box<dyn tokio::io::AsyncWrite + Send>> = match path {
+                    None       => Box::pin(tokio::io::stdout()),
+                    Some(path) => Box::pin(tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await
+                        .expect("failed to open ACCESS_LOG_PATH")
+                    ),
+                };
+
+                let (mut batch, mut buf) = (Vec::with_capacity(BATCH_SIZE), Vec::new());
+                while rx.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+                    for line in batch.drain(..) {
+                        buf.extend_from_slice(line.as_bytes());
+                    }
+                    if let Err(e) = async {out.write_all(&buf).await?; out.flush().await}.await {
+                        eprintln!("failed to write access log: {e}");
+                    }
+                    buf.clear();
+                }
+            });
+
+            Self { format, sample, counter: Arc::new(AtomicU64::new(0)), lines }
+        }
+    }
+
+    impl<I: FangProc> Fang<I> for AccessLog {
+        type Proc = AccessLogProc<I>;
+        fn chain(&self, inner: I) -> Self::Proc {
+            AccessLogProc { log: self.clone(), inner }
+        }
+    }
+
+    pub struct AccessLogProc<I> {
+        log:   AccessLog,
+        inner: I,
+    }
+
+    impl<I: FangProc> FangProc for AccessLogProc<I> {
+        async fn bite<'b>(&'b self, req: &'b mut Request) -> Response {
+            if self.log.counter.fetch_add(1, Ordering::Relaxed) % self.log.sample != 0 {
+                return self.inner.bite(req).await
+            }
+
+            let start = Instant::now();
+            let res = self.inner.bite(req).await;
+            let latency = start.elapsed();
+
+            let line = format_line(self.log.format, req, PROTOCOL, &res, latency.as_micros());
+            if self.log.lines.try_send(line).is_err() {
+                /* the writer is behind: drop the line rather than block the request */
+            }
+
+            res
+        }
+    }
+
+    /// The version of the requests: ohkami's `Request` doesn't keep it, as `howl`
+    /// and `howls` only serve HTTP/1.1.
+    const PROTOCOL: &str = "HTTP/1.1";
+
+    fn format_line(format: Format, req: &Request, protocol: &str, res: &Response, latency_us: u128) -> String {
+        let (now, ip) = (SystemTime::now(), req.ip);
+        let (method, path) = (req.method.as_str(), req.path.str());
+        let status = res.status.code();
+        let bytes  = res.payload().map_or(0, <[u8]>::len);
+
+        match format {
+            Format::Common => format!(
+                "{ip} - - [{}] \"{method} {path} {protocol}\" {status} {bytes}\n",
+                clf_time(now),
+            ),
+            Format::Combined => format!(
+                "{ip} - - [{}] \"{method} {path} {protocol}\" {status} {bytes} \"{}\" \"{}\"\n",
+                clf_time(now),
+                req.headers.Referer().unwrap_or("-"),
+                req.headers.UserAgent().unwrap_or("-"),
+            ),
+            Format::JSON => format!(
+                "{{\"time\":{},\"ip\":\"{ip}\",\"method\":\"{method}\",\"path\":{},\"status\":{status},\"bytes\":{bytes},\"latency_us\":{latency_us},\"user_agent\":{}}}\n",
+                now.duration_since(UNIX_EPOCH).unwrap().as_millis(),
+                json_string(path),
+                json_string(req.headers.UserAgent().unwrap_or("")),
+            ),
+        }
+    }
+
+    /// `s` quoted and escaped as a JSON string
+    fn json_string(s: &str) -> String {
+        let mut json = String::with_capacity(s.len() + 2);
+        json.push('"');
+        for c in s.chars() {
+            match c {
+                '"'  => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if c.is_control() => {let _ = write!(json, "\\u{:04x}", c as u32);}
+                c    => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }
+
+    /// `10/Oct/2000:13:55:36 +0000`
+    fn clf_time(time: SystemTime) -> String {
+        const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+        let secs = time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+        /* http://howardhinnant.github.io/date_algorithms.html#civil_from_days */
+        let z   = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp  = (5 * doy + 2) / 153;
+        let day   = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 {mp + 3} else {mp - 9};
+        let year  = yoe + era * 400 + (month <= 2) as i64;
+
+        format!(
+            "{day:02}/{}/{year}:{:02}:{:02}:{:02} +0000",
+            MONTHS[month as usize - 1],
+            secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60,
+        )
+    }
+}
+
+/// Reports the allocations made while serving each request
+/// as `X-Alloc-Count` and `X-Alloc-Bytes` response headers.
+#[cfg(feature = "alloc-count")]
+pub use count_allocs::CountAllocs;
+#[cfg(feature = "alloc-count")]
+mod count_allocs {
+    use ohkami::{Fang, FangProc, Request, Response};
+
+    #[derive(Clone)]
+    pub struct CountAllocs;
+
+    impl<I: FangProc> Fang<I> for CountAllocs {
+        type Proc = CountAllocsProc<I>;
+        fn chain(&self, inner: I) -> Self::Proc {
+            CountAllocsProc { inner }
+        }
+    }
+
+    pub struct CountAllocsProc<I> {
+        inner: I,
+    }
+
+    impl<I: FangProc> FangProc for CountAllocsProc<I> {
+        async fn bite<'b>(&'b self, req: &'b mut Request) -> Response {
+            let (mut res, (count, bytes)) = crate::alloc::counting::count(self.inner.bite(req)).await;
+            res.headers.set()
+                .x("X-Alloc-Count", count.to_string())
+                .x("X-Alloc-Bytes", bytes.to_string());
+            res
+        }
+    }
+}
+
+/// `Accept-Encoding` negotiation (gzip, br, zstd) for `COMPRESSED_PATHS`.
+/// Bodies smaller than `COMPRESSION_MIN_SIZE` bytes (1024 by default)
+/// are sent as they are.
+#[cfg(feature = "compression")]
+pub use compress::Compress;
+#[cfg(feature = "compression")]
+mod compress {
+    use ohkami::{Fang, FangProc, Request, Response};
+    use std::io::Write;
+
+    const COMPRESSED_PATHS: [&str; 3] = ["/fortunes", "/queries", "/updates"];
+
+    #[derive(Clone)]
+    pub struct Compress {
+        min_size: usize,
+    }
+
+    impl Compress {
+        pub fn new() -> Self {
+            let min_size = std::env::var("COMPRESSION_MIN_SIZE")
+                .map(|n| n.parse().expect("invalid COMPRESSION_MIN_SIZE"))
+                .unwrap_or(1024);
+            Self { min_size }
+        }
+    }
+
+    impl<I: FangProc> Fang<I> for Compress {
+        type Proc = CompressProc<I>;
+        fn chain(&self, inner: I) -> Self::Proc {
+            CompressProc { min_size: self.min_size, inner }
+        }
+    }
+
+    pub struct CompressProc<I> {
+        min_size: usize,
+        inner:    I,
+    }
+
+    impl<I: FangProc> FangProc for CompressProc<I> {
+        async fn bite<'b>(&'b self, req: &'b mut Request) -> Response {
+            let encoding = COMPRESSED_PATHS.contains(&req.path.str())
+                .then(|| req.headers.AcceptEncoding().and_then(Encoding::negotiate))
+                .flatten();
+
+            let mut res = self.inner.bite(req).await;
+
+            if let Some(encoding) = encoding {
+                if let Some(body) = res.payload().filter(|body| body.len() >= self.min_size) {
+                    let compressed = encoding.compress(body);
+                    let content_type = res.headers.ContentType().unwrap_or("application/octet-stream").to_owned();
+                    /* `set_payload` only takes a `&'static str`: restore the original Content-Type after it */
+                    res.set_payload("application/octet-stream", compressed);
+                    res.headers.set()
+                        .ContentType(content_type)
+                        .ContentEncoding(encoding.name())
+                        .Vary("Accept-Encoding");
+                }
+            }
+
+            res
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Encoding { Gzip, Br, Zstd }
+
+    impl Encoding {
+        /// in order of preference when the client has no preference
+        const SUPPORTED: [Self; 3] = [Self::Br, Self::Zstd, Self::Gzip];
+
+        fn name(self) -> &'static str {
+            match self {
+                Self::Gzip => "gzip",
+                Self::Br   => "br",
+                Self::Zstd => "zstd",
+            }
+        }
+
+        /// The supported encoding with the highest q-value, if any.
+        /// An encoding's own q-value takes precedence over `*`'s.
+        fn negotiate(accept_encoding: &str) -> Option<Self> {
+            let q_of = |encoding: Self| {
+                let (mut explicit, mut wildcard) = (None, None);
+                for item in accept_encoding.split(',') {
+                    let mut params = item.split(';').map(str::trim);
+                    let coding = params.next().unwrap_or_default();
+                    let q = params
+                        .find_map(|p| p.strip_prefix("q="))
+                        .map_or(1., |q| q.parse().unwrap_or(0.));
+                    if coding.eq_ignore_ascii_case(encoding.name()) {
+                        explicit = Some(explicit.map_or(q, |e: f32| e.max(q)));
+                    } else if coding == "*" {
+                        wildcard = Some(q);
+                    }
+                }
+                explicit.or(wildcard).unwrap_or(0.)
+            };
+
+            /* `SUPPORTED` is in order of preference, so only a higher q wins */
+            let mut best: Option<(Self, f32)> = None;
+            for encoding in Self::SUPPORTED {
+                let q = q_of(encoding);
+                if q > 0. && best.is_none_or(|(_, best_q)| q > best_q) {
+                    best = Some((encoding, q));
+                }
+            }
+            best.map(|(encoding, _)| encoding)
+        }
+
+        fn compress(self, body: &[u8]) -> Vec<u8> {
+            match self {
+                Self::Gzip => {
+                    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                    encoder.write_all(body).unwrap();
+                    encoder.finish().unwrap()
+                }
+                Self::Br => {
+                    /* same quality as tower-http's default */
+                    let mut compressed = Vec::new();
+                    let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 4, 22);
+                    encoder.write_all(body).unwrap();
+                    drop(encoder);
+                    compressed
+                }
+                Self::Zstd => {
+                    zstd::bulk::compress(body, zstd::DEFAULT_COMPRESSION_LEVEL).unwrap()
+                }
+            }
+        }
+    }
+}
+
+/// Wraps the app in `MIDDLEWARES` (8 by default) pairs of a no-op fang and
+/// a fang overwriting `X-Middleware`, to measure the cost of composing fangs.
+#[cfg(feature = "middleware-stack")]
+pub use middleware_stack::stack;
+#[cfg(feature = "middleware-stack")]
+mod middleware_stack {
+    use ohkami::prelude::*;
+
+    #[derive(Clone)]
+    struct NoOp;
+    impl FangAction for NoOp {}
+
+    #[derive(Clone)]
+    struct SetHeader;
+    impl FangAction for SetHeader {
+        #[inline(always)]
+        async fn back<'a>(&'a self, res: &'a mut Response) {
+            res.headers.set().x("X-Middleware", "ohkami");
+        }
+    }
+
+    pub fn stack(o: Ohkami) -> Ohkami {
+        let n: usize = std::env::var("MIDDLEWARES")
+            .map(|n| n.parse().expect("invalid MIDDLEWARES"))
+            .unwrap_or(8);
+
+        (0..n).fold(o, |o, _| Ohkami::new((NoOp, SetHeader, "/".By(o))))
+    }
+}
@@ -0,0 +1,150 @@
+//! JSON response bodies serialized by `simd-json`, `sonic-rs` or a hand-rolled
+//! `itoa`-based writer, selected by cargo features. Without any of them,
+//! ohkami's built-in `JSON` (serde_json) is used instead.
+
+#![cfg(any(feature = "simd-json", feature = "sonic-rs", feature = "itoa-json"))]
+
+use ohkami::{IntoResponse, Response};
+
+#[cfg(any(
+    all(feature = "simd-json", feature = "sonic-rs"),
+    all(feature = "simd-json", feature = "itoa-json"),
+    all(feature = "sonic-rs", feature = "itoa-json"),
+))]
+compile_error!("select at most one JSON serializer of `simd-json`, `sonic-rs` and `itoa-json`");
+
+pub struct JSON<T>(pub T);
+
+#[cfg(feature = "simd-json")]
+impl<T: ohkami::serde::Serialize> IntoResponse for JSON<T> {
+    #[inline]
+    fn into_response(self) -> Response {
+        match simd_json::to_vec(&self.0) {
+            Ok(json) => Response::OK().with_payload("application/json", json),
+            Err(_)   => Response::InternalServerError(),
+        }
+    }
+}
+
+#[cfg(feature = "sonic-rs")]
+impl<T: ohkami::serde::Serialize> IntoResponse for JSON<T> {
+    #[inline]
+    fn into_response(self) -> Response {
+        match sonic_rs::to_vec(&self.0) {
+            Ok(json) => Response::OK().with_payload("application/json", json),
+            Err(_)   => Response::InternalServerError(),
+        }
+    }
+}
+
+#[cfg(feature = "itoa-json")]
+impl<T: WriteJSON> IntoResponse for JSON<T> {
+    #[inline]
+    fn into_response(self) -> Response {
+        let mut json = Vec::with_capacity(self.0.size_hint());
+        self.0.write_json(&mut json);
+        Response::OK().with_payload("application/json", json)
+    }
+}
+
+#[cfg(feature = "itoa-json")]
+pub use itoa_json::WriteJSON;
+#[cfg(feature = "itoa-json")]
+mod itoa_json {
+    use crate::models::{Fortune, Message, World};
+
+    pub trait WriteJSON {
+        fn size_hint(&self) -> usize;
+        fn write_json(&self, buf: &mut Vec<u8>);
+    }
+
+    impl WriteJSON for Message {
+        #[inline]
+        fn size_hint(&self) -> usize {
+            br#"{"message":""}"#.len() + self.message.len()
+        }
+        #[inline]
+        fn write_json(&self, buf: &mut Vec<u8>) {
+            buf.extend_from_slice(br#"{"message":"#);
+            write_str(self.message, buf);
+            buf.push(b'}');
+        }
+    }
+
+    impl WriteJSON for World {
+        #[inline]
+        fn size_hint(&self) -> usize {
+            br#"{"id":10000,"randomNumber":10000}"#.len()
+        }
+        #[inline]
+        fn write_json(&self, buf: &mut Vec<u8>) {
+            let mut itoa = itoa::Buffer::new();
+            buf.extend_from_slice(br#"{"id":"#);
+            buf.extend_from_slice(itoa.format(self.id).as_bytes());
+            buf.extend_from_slice(br#","randomNumber":"#);
+            buf.extend_from_slice(itoa.format(self.randomnumber).as_bytes());
+            buf.push(b'}');
+        }
+    }
+
+    impl WriteJSON for Fortune<'_> {
+        #[inline]
+        fn size_hint(&self) -> usize {
+            br#"{"id":10000,"message":""}"#.len() + self.message.len()
+        }
+        #[inline]
+        fn write_json(&self, buf: &mut Vec<u8>) {
+            let mut itoa = itoa::Buffer::new();
+            buf.extend_from_slice(br#"{"id":"#);
+            buf.extend_from_slice(itoa.format(self.id).as_bytes());
+            buf.extend_from_slice(br#","message":"#);
+            write_str(self.message, buf);
+            buf.push(b'}');
+        }
+    }
+
+    impl<T: WriteJSON> WriteJSON for Vec<T> {
+        #[inline]
+        fn size_hint(&self) -> usize {
+            2 + self.iter().map(|t| t.size_hint() + 1).sum::<usize>()
+        }
+        #[inline]
+        fn write_json(&self, buf: &mut Vec<u8>) {
+            buf.push(b'[');
+            for (i, t) in self.iter().enumerate() {
+                if i != 0 {buf.push(b',')}
+                t.write_json(buf);
+            }
+            buf.push(b']');
+        }
+    }
+
+    fn write_str(s: &str, buf: &mut Vec<u8>) {
+        buf.push(b'"');
+        let mut start = 0;
+        for (i, b) in s.bytes().enumerate() {
+            let escaped: &[u8] = match b {
+                b'"'  => br#"\""#,
+                b'\\' => br"\\",
+                b'\n' => br"\n",
+                b'\r' => br"\r",
+                b'\t' => br"\t",
+                0x00..=0x1f => {
+                    buf.extend_from_slice(&s.as_bytes()[start..i]);
+                    buf.extend_from_slice(br"\u00");
+                    buf.extend_from_slice(&[HEX[(b >> 4) as usize], HEX[(b & 0xf) as usize]]);
+                    start = i + 1;
+                    continue
+                }
+                _ => continue,
+            };
+            buf.extend_from_slice(&s.as_bytes()[start..i]);
+            buf.extend_from_slice(escaped);
+            start = i + 1;
+        }
+        buf.extend_from_slice(&s.as_bytes()[start..]);
+        buf.push(b'"');
+    }
+
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+}
@@ -1,14 +1,33 @@
+mod alloc;
+mod api;
 mod fangs;
+mod json;
+#[cfg(feature = "many-routes")]
+mod many_routes;
 mod models;
 mod postgres;
+#[cfg(feature = "pprof")]
+mod profiling;
 mod templates;
+#[cfg(feature = "sse")]
+mod sse;
+#[cfg(feature = "static-files")]
+mod static_files;
+#[cfg(feature = "tls")]
+mod tls;
+#[cfg(feature = "ws")]
+mod ws;
 
 use {
     fangs::SetServer,
     models::Message,
     ohkami::prelude::*,
-    ohkami::format::JSON,
+    ohkami::IntoResponse,
 };
+#[cfg(not(any(feature = "simd-json", feature = "sonic-rs", feature = "itoa-json")))]
+use ohkami::format::JSON;
+#[cfg(any(feature = "simd-json", feature = "sonic-rs", feature = "itoa-json"))]
+use json::JSON;
 use {
     models::{Fortune, World, WorldsMeta},
     postgres::Postgres,
@@ -18,6 +37,10 @@ use {
 
 /// ref: https://github.com/TechEmpower/FrameworkBenchmarks/blob/38c565ebaa900b4db51c0425d11a6619a5615a79/frameworks/Rust/axum/src/server.rs
 fn main() {
+    if let Ok(iterations) = std::env::var("RENDER_BENCH") {
+        return templates::bench_render(iterations.parse().expect("invalid RENDER_BENCH"));
+    }
+
     fn runtime() -> tokio::runtime::Runtime {
         tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -26,6 +49,15 @@ fn main() {
     }
 
     async fn serve(o: Ohkami) -> std::io::Result<()> {
+        /* ohkami's `howl` only accepts a TCP listener, with no per-connection API to serve a `UnixStream` */
+        if let Ok(path) = std::env::var("UNIX_SOCKET") {
+            panic!("UNIX_SOCKET `{path}` is not supported: ohkami can only listen on TCP");
+        }
+        /* nor a hook around its accepts to hold them back */
+        if let Ok(max) = std::env::var("MAX_CLIENT_CONNECTIONS") {
+            panic!("MAX_CLIENT_CONNECTIONS `{max}` is not supported: ohkami accepts connections by itself");
+        }
+
         println!("start serving !");
 
         let socket = tokio::net::TcpSocket::new_v4()?;
@@ -34,34 +66,127 @@ fn main() {
         socket.set_nodelay(true)?;
 
         socket.bind("0.0.0.0:8000".parse().unwrap())?;
+        #[cfg(not(feature = "tls"))]
         o.howl(socket.listen(4096)?).await;
+        #[cfg(feature = "tls")]
+        o.howls(socket.listen(4096)?, tls::config()).await;
 
         Ok(())
     }
 
-    for _ in 0..(num_cpus::get() - 1/*for main thread*/) {
-        std::thread::spawn(|| {
-            runtime().block_on(async {
-                serve(ohkami().await).await.expect("serving error")
-            });
-        });
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_thread_names(true)
+        .with_thread_ids(true)
+        .init();
+
+    for i in 1..num_cpus::get()/* 0 for main thread */ {
+        std::thread::Builder::new()
+            .name(format!("runtime-{i}"))
+            .spawn(|| {
+                runtime().block_on(async {
+                    serve(ohkami().await).await.expect("serving error")
+                });
+            })
+            .expect("failed to spawn a runtime thread");
     }
     runtime().block_on(async {
+        #[cfg(feature = "query-stats")]
+        tokio::spawn(async {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let mut terminate = signal(SignalKind::terminate()).unwrap();
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => (),
+                _ = terminate.recv()        => (),
+            }
+            println!("{}", postgres::stats::summary());
+            std::process::exit(0)
+        });
+
         serve(ohkami().await).await.expect("serving error")
     });
 }
 
 pub async fn ohkami() -> Ohkami {
-    Ohkami::new((
+    let o = Ohkami::new((
         SetServer,
         Context::new(Postgres::new().await),
-        "/json"     .GET(json_serialization),
-        "/db"       .GET(single_database_query),
-        "/queries"  .GET(multiple_database_query),
-        "/fortunes" .GET(fortunes),
-        "/update"   .GET(database_updates),
-        "/plaintext".GET(plaintext),
-    ))
+        "/json"          .GET(json_serialization),
+        "/db"            .GET(single_database_query),
+        "/queries"       .GET(multiple_database_query),
+        "/fortunes"      .GET(fortunes),
+        "/updates"       .GET(database_updates),
+        "/plaintext"     .GET(plaintext),
+        "/worlds/batch"  .POST(save_worlds_batch),
+        "/worlds/:id"    .GET(world_by_id),
+        "/fortunes/:id"  .GET(fortune_by_id),
+        "/a/:x/b/:y/c/:z".GET(nested_params),
+        "/api/worlds"    .By(api::ohkami()),
+    ));
+
+    #[cfg(feature = "many-routes")]
+    let o = Ohkami::new((
+        "/".By(many_routes::ohkami()),
+        "/".By(o),
+    ));
+
+    #[cfg(feature = "query-stats")]
+    let o = Ohkami::new((
+        "/admin/query-stats".GET(query_stats),
+        "/".By(o),
+    ));
+
+    #[cfg(feature = "pprof")]
+    let o = Ohkami::new((
+        "/debug/pprof/profile".GET(profiling::profile),
+        "/".By(o),
+    ));
+
+    #[cfg(feature = "static-files")]
+    let o = Ohkami::new((
+        "/static/:name".GET(static_files::static_file),
+        "/".By(o),
+    ));
+
+    #[cfg(feature = "sse")]
+    let o = Ohkami::new((
+        "/sse".GET(sse::sse),
+        "/".By(o),
+    ));
+
+    #[cfg(feature = "ws")]
+    let o = Ohkami::new((
+        "/ws/echo"     .GET(ws::echo),
+        "/ws/broadcast".GET(ws::broadcast),
+        "/".By(o),
+    ));
+
+    /* requests are only timed out if `REQUEST_TIMEOUT_MS` is set, */
+    let o = match fangs::RequestTimeout::from_env() {
+        Some(timeout) => Ohkami::new((timeout, "/".By(o))),
+        None          => o,
+    };
+    /* and in flight requests only capped if `MAX_IN_FLIGHT` is */
+    let o = match fangs::LimitInFlight::from_env() {
+        Some(limit) => Ohkami::new((limit, "/".By(o))),
+        None        => o,
+    };
+
+    /* optional fangs wrap the whole app, and are compiled out if disabled */
+    #[cfg(feature = "middleware-stack")]
+    let o = fangs::stack(o);
+    #[cfg(feature = "compression")]
+    let o = Ohkami::new((fangs::Compress::new(), "/".By(o)));
+    #[cfg(feature = "access-log")]
+    let o = Ohkami::new((fangs::AccessLog::new(), "/".By(o)));
+    #[cfg(feature = "tracing")]
+    let o = Ohkami::new((fangs::Trace, "/".By(o)));
+    #[cfg(feature = "alloc-count")]
+    let o = Ohkami::new((fangs::CountAllocs, "/".By(o)));
+
+    o
 }
 
 async fn json_serialization() -> JSON<Message> {
@@ -88,14 +213,18 @@ async fn multiple_database_query(
 
 async fn fortunes(
     Context(db): Context<'_, Postgres>,
-) -> FortunesTemplate {
-    let mut fortunes = db.select_all_fortunes().await;
+) -> Response {
+    let rows = db.select_all_fortunes().await;
+
+    let mut fortunes = rows.fortunes(1);
     fortunes.push(Fortune {
         id:      0,
-        message: String::from("Additional fortune added at request time."),
+        message: "Additional fortune added at request time.",
     });
-    fortunes.sort_unstable_by(|a, b| str::cmp(&a.message, &b.message));
-    FortunesTemplate { fortunes }
+    fortunes.sort_unstable_by(|a, b| str::cmp(a.message, b.message));
+
+    /* render while `rows` is still alive */
+    FortunesTemplate { fortunes }.into_response()
 }
 
 async fn database_updates(
@@ -107,6 +236,63 @@ async fn database_updates(
     JSON(worlds)
 }
 
+/// The body is parsed by ohkami's `JSON` (serde_json) whichever serializer
+/// is selected, as the JSON features only replace the response serializer.
+async fn save_worlds_batch(
+    Context(db): Context<'_, Postgres>,
+    ohkami::format::JSON(worlds): ohkami::format::JSON<Vec<World>>,
+) -> Response {
+    let (ids, randomnumbers) = match World::validate_batch(&worlds) {
+        Ok(batch)    => batch,
+        Err(message) => return Response::UnprocessableEntity().with_text(message),
+    };
+
+    db.save_worlds(&ids, &randomnumbers).await;
+
+    JSON(worlds).into_response()
+}
+
+async fn world_by_id(
+    id: u32,
+    Context(db): Context<'_, Postgres>,
+) -> Response {
+    let Ok(id) = i32::try_from(id) else {
+        return Response::NotFound()
+    };
+    match db.select_world_by_id(id).await {
+        Some(world) => JSON(world).into_response(),
+        None        => Response::NotFound(),
+    }
+}
+
+async fn fortune_by_id(
+    id: u32,
+    Context(db): Context<'_, Postgres>,
+) -> Response {
+    let Ok(id) = i32::try_from(id) else {
+        return Response::NotFound()
+    };
+    let rows = db.select_fortune_by_id(id).await;
+
+    /* serialize while `rows` is still alive */
+    match rows.first() {
+        Some(fortune) => JSON(fortune).into_response(),
+        None          => Response::NotFound(),
+    }
+}
+
+/// Only exercises the router's path parameters, with no I/O.
+async fn nested_params(
+    (x, y, z): (&str, &str, &str),
+) -> String {
+    format!("x={x}, y={y}, z={z}")
+}
+
 async fn plaintext() -> &'static str {
     "Hello, World!"
 }
+
+#[cfg(feature = "query-stats")]
+async fn query_stats() -> Response {
+    Response::OK().with_payload("application/json", postgres::stats::summary())
+}
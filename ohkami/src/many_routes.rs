@@ -0,0 +1,14 @@
+//! `ROUTES` static and parameterized routes with shared prefixes under `/r`,
+//! generated by `build.rs`, to measure how the router scales with its size.
+
+use ohkami::prelude::*;
+
+include!(concat!(env!("OUT_DIR"), "/many_routes.rs"));
+
+async fn static_route() -> &'static str {
+    "static"
+}
+
+async fn param_route(_id: &str) -> &'static str {
+    "param"
+}
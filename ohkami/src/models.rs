@@ -9,12 +9,15 @@ pub use db::*;
 mod db {
     use super::*;
 
-    pub struct Fortune {
+    /// Borrows its `message` from the row it was decoded from,
+    /// so no `String` is allocated per fortune.
+    #[derive(serde::Serialize)]
+    pub struct Fortune<'r> {
         pub id:      i32,
-        pub message: String,
+        pub message: &'r str,
     }
 
-    #[derive(serde::Serialize)]
+    #[derive(serde::Serialize, serde::Deserialize)]
     pub struct World {
         pub id:           i32,
         #[serde(rename = "randomNumber")]
@@ -35,4 +38,139 @@ mod db {
             }
         }
     }
+
+    impl World {
+        /// Most worlds accepted by `POST /worlds/batch`, as by `/updates`.
+        pub const MAX_BATCH: usize = 500;
+
+        /// Checks a batch of worlds to save, returning their ids and random numbers
+        /// sorted by id, so that concurrent batches lock their rows in the same
+        /// order rather than deadlock. Both must be in 1..=10000, and each id may
+        /// only appear once.
+        pub fn validate_batch(worlds: &[World]) -> Result<(Vec<i32>, Vec<i32>), String> {
+            if worlds.is_empty() || worlds.len() > Self::MAX_BATCH {
+                return Err(format!("expected 1 to {} worlds, got {}", Self::MAX_BATCH, worlds.len()))
+            }
+
+            let mut pairs = Vec::with_capacity(worlds.len());
+            for w in worlds {
+                if !(1..=10000).contains(&w.id) || !(1..=10000).contains(&w.randomnumber) {
+                    return Err(format!("`id` and `randomNumber` must be in 1..=10000, got {} and {}", w.id, w.randomnumber))
+                }
+                pairs.push((w.id, w.randomnumber));
+            }
+
+            pairs.sort_unstable_by_key(|&(id, _)| id);
+            if let Some(dup) = pairs.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(format!("duplicate id {}", dup[0].0))
+            }
+
+            Ok(pairs.into_iter().unzip())
+        }
+    }
+
+    #[cfg(feature = "sse")]
+    #[derive(serde::Deserialize)]
+    pub struct SseMeta<'req> {
+        events:      Option<&'req str>,
+        interval_ms: Option<&'req str>,
+    }
+    #[cfg(feature = "sse")]
+    impl SseMeta<'_> {
+        /// Number of events (1..=100000, 10 by default) and the interval
+        /// between them (up to 60s, 100ms by default).
+        pub fn parse(self) -> (usize, std::time::Duration) {
+            let events = match self.events.unwrap_or("10").parse::<usize>().unwrap_or(10) {
+                n @ 1..=100000 => n,
+                0              => 1,
+                100001..       => 100000,
+            };
+            let interval_ms = self.interval_ms.unwrap_or("100").parse::<u64>().unwrap_or(100).min(60000);
+            (events, std::time::Duration::from_millis(interval_ms))
+        }
+    }
+
+    /// Body of `POST /api/worlds` and `PUT`/`PATCH /api/worlds/:id`.
+    #[derive(serde::Deserialize)]
+    pub struct WorldInput {
+        id:           Option<i32>,
+        #[serde(rename = "randomNumber")]
+        randomnumber: Option<i32>,
+    }
+    impl WorldInput {
+        fn check_randomnumber(randomnumber: i32) -> Result<i32, String> {
+            match randomnumber {
+                n @ 1..=10000 => Ok(n),
+                n             => Err(format!("`randomNumber` must be in 1..=10000, got {n}")),
+            }
+        }
+
+        fn check_id(&self, path_id: i32) -> Result<(), String> {
+            match self.id {
+                Some(id) if id != path_id => Err(format!("`id` {id} doesn't match the path's {path_id}")),
+                _                         => Ok(()),
+            }
+        }
+
+        /// The id (assigned by Postgres if omitted) and the random number of a new world.
+        pub fn validate_new(&self) -> Result<(Option<i32>, i32), String> {
+            if let Some(id @ ..=0) = self.id {
+                return Err(format!("`id` must be positive, got {id}"))
+            }
+            let randomnumber = self.randomnumber.ok_or("`randomNumber` is required")?;
+            Ok((self.id, Self::check_randomnumber(randomnumber)?))
+        }
+
+        /// The random number replacing the world `id`'s.
+        pub fn validate_put(&self, id: i32) -> Result<i32, String> {
+            self.check_id(id)?;
+            let randomnumber = self.randomnumber.ok_or("`randomNumber` is required")?;
+            Self::check_randomnumber(randomnumber)
+        }
+
+        /// The random number to set to the world `id`, if any.
+        pub fn validate_patch(&self, id: i32) -> Result<Option<i32>, String> {
+            self.check_id(id)?;
+            self.randomnumber.map(Self::check_randomnumber).transpose()
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    pub struct WorldsPageMeta<'req> {
+        limit:  Option<&'req str>,
+        offset: Option<&'req str>,
+        after:  Option<&'req str>,
+    }
+    impl WorldsPageMeta<'_> {
+        /// `(after, limit, offset)` of `GET /api/worlds`: either `offset`
+        /// (0 by default) or the keyset cursor `after` (the last id of the
+        /// previous page), with `limit` in 1..=100 (20 by default).
+        pub fn parse(self) -> Result<(i32, i64, i64), String> {
+            fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+                value.parse().map_err(|_| format!("invalid `{name}`: {value}"))
+            }
+
+            let limit = match self.limit.map(|l| parse::<i64>("limit", l)).transpose()? {
+                None              => 20,
+                Some(l @ 1..=100) => l,
+                Some(l)           => return Err(format!("`limit` must be in 1..=100, got {l}")),
+            };
+            match (self.after, self.offset) {
+                (Some(_), Some(_))  => Err("`after` and `offset` can't be used together".into()),
+                (Some(after), None) => Ok((parse("after", after)?, limit, 0)),
+                (None, offset)      => match offset.map(|o| parse::<i64>("offset", o)).transpose()? {
+                    Some(o @ ..0) => Err(format!("`offset` must not be negative, got {o}")),
+                    o             => Ok((0, limit, o.unwrap_or(0))),
+                },
+            }
+        }
+    }
+
+    /// A page of `GET /api/worlds`, with the cursor of the next one
+    /// if it may not be the last.
+    #[derive(serde::Serialize)]
+    pub struct WorldsPage {
+        pub worlds:     Vec<World>,
+        pub next_after: Option<i32>,
+    }
 }
@@ -0,0 +1,91 @@
+//! Data access for `/api/worlds`, on the `ApiWorld` table apart from the
+//! TechEmpower endpoints' `World`.
+
+use super::Postgres;
+#[cfg(feature = "query-stats")]
+use super::stats;
+use crate::models::World;
+
+pub(super) const SELECT_API_WORLD: &str = "SELECT id, randomnumber FROM apiworld WHERE id = $1";
+/// Keyset pagination by `id > $1`, which is also used with `$1 = 0` for `OFFSET`.
+pub(super) const LIST_API_WORLDS: &str = "SELECT id, randomnumber FROM apiworld WHERE id > $1 ORDER BY id LIMIT $2 OFFSET $3";
+/// Returns no row if the id is taken.
+pub(super) const INSERT_API_WORLD: &str = "\
+    INSERT INTO apiworld (id, randomnumber) \
+    VALUES (COALESCE($1::int, nextval(pg_get_serial_sequence('apiworld', 'id'))::int), $2) \
+    ON CONFLICT (id) DO NOTHING \
+    RETURNING id, randomnumber \
+";
+/// Keeps the random number if `$2` is `NULL`.
+pub(super) const UPDATE_API_WORLD: &str = "UPDATE apiworld SET randomnumber = COALESCE($2::int, randomnumber) WHERE id = $1 RETURNING id, randomnumber";
+pub(super) const DELETE_API_WORLD: &str = "DELETE FROM apiworld WHERE id = $1";
+
+impl Postgres {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "select_api_world", skip(self)))]
+    pub async fn select_api_world(&self, id: i32) -> Option<World> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let world = self.client.select_api_world(id).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::SelectApiWorld, start.elapsed(), || format!("$1 = {id}"));
+
+        world
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "list_api_worlds", skip(self)))]
+    pub async fn list_api_worlds(&self, after: i32, limit: i64, offset: i64) -> Vec<World> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let worlds = self.client.list_api_worlds(after, limit, offset).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::ListApiWorlds, start.elapsed(), || format!("$1 = {after}, $2 = {limit}, $3 = {offset}"));
+
+        worlds
+    }
+
+    /// The inserted world, or `None` if `id` is taken.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "insert_api_world", skip(self)))]
+    pub async fn insert_api_world(&self, id: Option<i32>, randomnumber: i32) -> Option<World> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let world = self.client.insert_api_world(id, randomnumber).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::InsertApiWorld, start.elapsed(), || format!("$1 = {id:?}, $2 = {randomnumber}"));
+
+        world
+    }
+
+    /// The updated world, or `None` if there's no such world.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "update_api_world", skip(self)))]
+    pub async fn update_api_world(&self, id: i32, randomnumber: Option<i32>) -> Option<World> {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let world = self.client.update_api_world(id, randomnumber).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::UpdateApiWorld, start.elapsed(), || format!("$1 = {id}, $2 = {randomnumber:?}"));
+
+        world
+    }
+
+    /// Whether the world existed.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "delete_api_world", skip(self)))]
+    pub async fn delete_api_world(&self, id: i32) -> bool {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let deleted = self.client.delete_api_world(id).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::DeleteApiWorld, start.elapsed(), || format!("$1 = {id}"));
+
+        deleted
+    }
+}
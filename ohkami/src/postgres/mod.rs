@@ -0,0 +1,222 @@
+//! Data access for the TechEmpower endpoints (and `/api/worlds` in `crud`).
+//! The driver is selected by cargo features (`sqlx`, `deadpool`), falling
+//! back to a single tokio-postgres client per runtime.
+//!
+//! The transport is selected by `DATABASE_URL`: TCP, a Unix socket directory
+//! as the host (`postgres://user:pass@%2Fvar%2Frun%2Fpostgresql/db`), or TLS
+//! with `sslmode=require` and the `postgres-tls` feature.
+
+#[cfg(not(any(feature = "sqlx", feature = "deadpool")))]
+mod with_tokio_postgres;
+#[cfg(not(any(feature = "sqlx", feature = "deadpool")))]
+use with_tokio_postgres::Client;
+
+#[cfg(feature = "sqlx")]
+mod with_sqlx;
+#[cfg(feature = "sqlx")]
+use with_sqlx::Client;
+
+#[cfg(feature = "deadpool")]
+mod with_deadpool;
+#[cfg(feature = "deadpool")]
+use with_deadpool::Client;
+
+mod crud;
+
+#[cfg(feature = "query-stats")]
+pub mod stats;
+
+#[cfg(all(feature = "postgres-tls", not(feature = "sqlx")))]
+mod tls;
+#[cfg(all(feature = "postgres-tls", not(feature = "sqlx")))]
+use tls::connector;
+/// TLS connector of tokio-postgres (and deadpool), or none without the `postgres-tls` feature.
+/// sqlx negotiates TLS by itself, with `sslmode` and `sslrootcert` in `DATABASE_URL`.
+#[cfg(not(any(feature = "postgres-tls", feature = "sqlx")))]
+fn connector() -> tokio_postgres::NoTls {
+    tokio_postgres::NoTls
+}
+
+#[cfg(all(feature = "sqlx", feature = "deadpool"))]
+compile_error!("select at most one Postgres driver of `sqlx` and `deadpool`");
+
+use crate::models::{World, Fortune};
+use std::sync::Arc;
+use futures_util::stream::{StreamExt, FuturesUnordered};
+use rand::{rngs::SmallRng, SeedableRng, Rng, distributions::Uniform, thread_rng};
+
+const SELECT_WORLD_BY_ID: &str = "SELECT id, randomnumber FROM world WHERE id = $1 LIMIT 1";
+const SELECT_ALL_FORTUNES: &str = "SELECT id, message FROM fortune";
+const SELECT_FORTUNE_BY_ID: &str = "SELECT id, message FROM fortune WHERE id = $1";
+const UPDATE_WORLDS: &str = "\
+    UPDATE world SET randomnumber = new.randomnumber FROM ( \
+        SELECT * FROM UNNEST($1::int[], $2::int[]) AS v(id, randomnumber) \
+    ) AS new WHERE world.id = new.id \
+";
+
+/// Size of the connection pool of each runtime, for the pooled drivers
+/// (`MAX_CONNECTIONS` and `MIN_CONNECTIONS`, 4 by default).
+#[cfg(any(feature = "sqlx", feature = "deadpool"))]
+fn pool_size(key: &str) -> u32 {
+    std::env::var(key).map(|n| n.parse().expect("invalid pool size")).unwrap_or(4)
+}
+
+#[derive(Clone)]
+pub struct Postgres {
+    client: Arc<Client>,
+    #[cfg(feature = "query-stats")]
+    stats:  Arc<stats::QueryStats>,
+}
+
+impl Postgres {
+    pub async fn new() -> Self {
+        let client = Client::connect(&std::env::var("DATABASE_URL").unwrap()).await;
+        Self {
+            client: Arc::new(client),
+            #[cfg(feature = "query-stats")]
+            stats:  stats::QueryStats::register(),
+        }
+    }
+}
+
+impl Postgres {
+    const ID_RANGE: std::ops::Range<i32> = 1..10001;
+    
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "select_world_by_id", skip(self)))]
+    async fn select_random_world_by_id(&self, id: i32) -> World {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let world = self.client.select_world_by_id(id).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::SelectWorldById, start.elapsed(), || format!("$1 = {id}"));
+
+        world
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "update_worlds", skip_all, fields(n = ids.len())))]
+    async fn update_worlds(&self, ids: &[i32], randomnumbers: &[i32]) {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        self.client.update_worlds(ids, randomnumbers).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::UpdateWorlds, start.elapsed(), || format!("$1 = {ids:?}, $2 = {randomnumbers:?}"));
+    }
+}
+
+impl Postgres {
+    pub async fn select_random_world(&self) -> World {
+        let mut rng = SmallRng::from_rng(&mut thread_rng()).unwrap();
+        self.select_random_world_by_id(rng.gen_range(Self::ID_RANGE)).await
+    }
+    
+    pub async fn select_n_random_worlds(&self, n: usize) -> Vec<World> {
+        let rng = SmallRng::from_rng(&mut thread_rng()).unwrap();
+
+        let selects = FuturesUnordered::new();
+        for id in rng.sample_iter(Uniform::new(Self::ID_RANGE.start, Self::ID_RANGE.end)).take(n) {
+            selects.push(self.select_random_world_by_id(id))
+        }
+
+        selects.collect::<Vec<World>>().await
+    }
+    
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "select_all_fortunes", skip(self)))]
+    pub async fn select_all_fortunes(&self) -> FortuneRows {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let rows = self.client.select_all_fortunes().await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::SelectAllFortunes, start.elapsed(), String::new);
+
+        rows
+    }
+    
+    /// The world of `id`, or `None` if there's no such world.
+    pub async fn select_world_by_id(&self, id: i32) -> Option<World> {
+        if !Self::ID_RANGE.contains(&id) {
+            return None
+        }
+        Some(self.select_random_world_by_id(id).await)
+    }
+
+    /// The fortune of `id` (in 0 or 1 row) is decoded by `FortuneRows::first`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "select_fortune_by_id", skip(self)))]
+    pub async fn select_fortune_by_id(&self, id: i32) -> FortuneRows {
+        #[cfg(feature = "query-stats")]
+        let start = std::time::Instant::now();
+
+        let rows = self.client.select_fortune_by_id(id).await;
+
+        #[cfg(feature = "query-stats")]
+        self.stats.record(stats::Statement::SelectFortuneById, start.elapsed(), || format!("$1 = {id}"));
+
+        rows
+    }
+
+    /// Saves the `randomnumbers` of the worlds `ids` by the batched `UPDATE ... UNNEST`.
+    pub async fn save_worlds(&self, ids: &[i32], randomnumbers: &[i32]) {
+        self.update_worlds(ids, randomnumbers).await
+    }
+
+    pub async fn update_randomnumbers_of_n_worlds(&self, n: usize) -> Vec<World> {
+        let rng = SmallRng::from_rng(&mut thread_rng()).unwrap();
+
+        let mut worlds = self.select_n_random_worlds(n).await;
+
+        let mut ids = Vec::with_capacity(n);
+        let new_randomnumbers = rng
+            .sample_iter(Uniform::new(Self::ID_RANGE.start, Self::ID_RANGE.end))
+            .take(n)
+            .collect::<Vec<_>>();
+        for i in 0..n {
+            worlds[i].randomnumber = new_randomnumbers[i];
+            ids.push(worlds[i].id);
+        }
+
+        self.update_worlds(&ids, &new_randomnumbers).await;
+
+        worlds
+    }
+}
+
+/// Raw rows of `SELECT id, message FROM fortune`, kept alive
+/// so that `Fortune`s can borrow their messages from the row buffers.
+pub struct FortuneRows(
+    #[cfg(not(feature = "sqlx"))] Vec<tokio_postgres::Row>,
+    #[cfg(feature = "sqlx")]      Vec<sqlx::postgres::PgRow>,
+);
+
+impl FortuneRows {
+    /// Decodes the rows without copying the messages. `extra` is reserved
+    /// for the fortunes added at request time.
+    pub fn fortunes(&self, extra: usize) -> Vec<Fortune<'_>> {
+        #[cfg(feature = "sqlx")]
+        use sqlx::Row as _;
+
+        let mut fortunes = Vec::with_capacity(self.0.len() + extra);
+        for row in &self.0 {
+            fortunes.push(Fortune {
+                id:      row.get(0),
+                message: row.get(1),
+            });
+        }
+        fortunes
+    }
+
+    /// Decodes the first row only, as fetched by id.
+    pub fn first(&self) -> Option<Fortune<'_>> {
+        #[cfg(feature = "sqlx")]
+        use sqlx::Row as _;
+
+        self.0.first().map(|row| Fortune {
+            id:      row.get(0),
+            message: row.get(1),
+        })
+    }
+}
@@ -0,0 +1,78 @@
+//! rustls connector for tokio-postgres (and deadpool) with the `postgres-tls`
+//! feature, used as `sslmode` in `DATABASE_URL` (`disable`, `prefer` or `require`) says.
+//!
+//! Like libpq, the server is authenticated against the root certificates at
+//! `PGSSLROOTCERT` if set, and only encrypted to otherwise.
+
+use std::sync::Arc;
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+pub(super) fn connector() -> MakeRustlsConnect {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("unsupported TLS protocol versions");
+
+    let config = match std::env::var("PGSSLROOTCERT") {
+        Ok(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in rustls_pemfile::certs(&mut std::io::BufReader::new(
+                std::fs::File::open(&path).expect("failed to open PGSSLROOTCERT")
+            )) {
+                roots.add(cert.expect("invalid certificate in PGSSLROOTCERT")).expect("invalid root certificate");
+            }
+            builder.with_root_certificates(roots).with_no_client_auth()
+        }
+        Err(_) => builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+            .with_no_client_auth(),
+    };
+
+    MakeRustlsConnect::new(config)
+}
+
+/// Accepts any server certificate, still checking the handshake signatures.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity:    &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name:   &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now:           UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert:    &CertificateDer<'_>,
+        dss:     &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert:    &CertificateDer<'_>,
+        dss:     &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
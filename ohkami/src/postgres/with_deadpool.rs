@@ -0,0 +1,147 @@
+use super::{connector, FortuneRows, SELECT_WORLD_BY_ID, SELECT_ALL_FORTUNES, SELECT_FORTUNE_BY_ID, UPDATE_WORLDS, pool_size};
+use super::crud::{SELECT_API_WORLD, LIST_API_WORLDS, INSERT_API_WORLD, UPDATE_API_WORLD, DELETE_API_WORLD};
+use crate::models::World;
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+
+fn world(row: &tokio_postgres::Row) -> World {
+    World {
+        id:           row.get(0),
+        randomnumber: row.get(1),
+    }
+}
+
+/// A deadpool of tokio-postgres connections per runtime. Statements are
+/// prepared once per connection and cached by deadpool.
+pub(super) struct Client {
+    pool: Pool,
+}
+
+impl Client {
+    pub(super) async fn connect(url: &str) -> Self {
+        let manager = Manager::from_config(
+            url.parse().expect("invalid DATABASE_URL"),
+            connector(),
+            ManagerConfig { recycling_method: RecyclingMethod::Fast },
+        );
+        let (max, min) = (pool_size("MAX_CONNECTIONS"), pool_size("MIN_CONNECTIONS"));
+        /* the warmup would wait forever for connections over the pool size */
+        assert!(min <= max, "MIN_CONNECTIONS ({min}) must not exceed MAX_CONNECTIONS ({max})");
+
+        let pool = Pool::builder(manager)
+            .max_size(max as usize)
+            .build()
+            .expect("failed to build connection pool");
+
+        /* open `MIN_CONNECTIONS` connections in advance */
+        let mut warmup = Vec::new();
+        for _ in 0..min {
+            warmup.push(pool.get().await.expect("failed to connect database"));
+        }
+        drop(warmup);
+
+        Self { pool }
+    }
+
+    pub(super) async fn select_world_by_id(&self, id: i32) -> World {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(SELECT_WORLD_BY_ID).await.unwrap();
+
+        let row = client
+            .query_one(&statement, &[&id])
+            .await
+            .expect("failed to fetch a world");
+
+        World {
+            id:           row.get(0),
+            randomnumber: row.get(1),
+        }
+    }
+
+    pub(super) async fn select_all_fortunes(&self) -> FortuneRows {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(SELECT_ALL_FORTUNES).await.unwrap();
+
+        let rows = client
+            .query(&statement, &[])
+            .await
+            .expect("failed to fetch fortunes");
+
+        FortuneRows(rows)
+    }
+
+    pub(super) async fn select_fortune_by_id(&self, id: i32) -> FortuneRows {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(SELECT_FORTUNE_BY_ID).await.unwrap();
+
+        let rows = client
+            .query(&statement, &[&id])
+            .await
+            .expect("failed to fetch a fortune");
+
+        FortuneRows(rows)
+    }
+
+    pub(super) async fn update_worlds(&self, ids: &[i32], randomnumbers: &[i32]) {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(UPDATE_WORLDS).await.unwrap();
+
+        client
+            .execute(&statement, &[&ids, &randomnumbers])
+            .await
+            .expect("failed to update worlds");
+    }
+
+    pub(super) async fn select_api_world(&self, id: i32) -> Option<World> {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(SELECT_API_WORLD).await.unwrap();
+
+        client
+            .query_opt(&statement, &[&id])
+            .await
+            .expect("failed to fetch a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn list_api_worlds(&self, after: i32, limit: i64, offset: i64) -> Vec<World> {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(LIST_API_WORLDS).await.unwrap();
+
+        client
+            .query(&statement, &[&after, &limit, &offset])
+            .await
+            .expect("failed to list worlds")
+            .iter().map(world).collect()
+    }
+
+    pub(super) async fn insert_api_world(&self, id: Option<i32>, randomnumber: i32) -> Option<World> {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(INSERT_API_WORLD).await.unwrap();
+
+        client
+            .query_opt(&statement, &[&id, &randomnumber])
+            .await
+            .expect("failed to insert a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn update_api_world(&self, id: i32, randomnumber: Option<i32>) -> Option<World> {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(UPDATE_API_WORLD).await.unwrap();
+
+        client
+            .query_opt(&statement, &[&id, &randomnumber])
+            .await
+            .expect("failed to update a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn delete_api_world(&self, id: i32) -> bool {
+        let client = self.pool.get().await.expect("failed to get a connection");
+        let statement = client.prepare_cached(DELETE_API_WORLD).await.unwrap();
+
+        client
+            .execute(&statement, &[&id])
+            .await
+            .expect("failed to delete a world") > 0
+    }
+}
@@ -0,0 +1,120 @@
+use super::{FortuneRows, SELECT_WORLD_BY_ID, SELECT_ALL_FORTUNES, SELECT_FORTUNE_BY_ID, UPDATE_WORLDS, pool_size};
+use super::crud::{SELECT_API_WORLD, LIST_API_WORLDS, INSERT_API_WORLD, UPDATE_API_WORLD, DELETE_API_WORLD};
+use crate::models::World;
+use sqlx::{Row, postgres::{PgPool, PgPoolOptions}};
+
+fn world(row: &sqlx::postgres::PgRow) -> World {
+    World {
+        id:           row.get(0),
+        randomnumber: row.get(1),
+    }
+}
+
+/// A sqlx pool per runtime. Statements are prepared and cached
+/// per connection by sqlx itself.
+pub(super) struct Client {
+    pool: PgPool,
+}
+
+impl Client {
+    pub(super) async fn connect(url: &str) -> Self {
+        let pool = PgPoolOptions::new()
+            .max_connections(pool_size("MAX_CONNECTIONS"))
+            .min_connections(pool_size("MIN_CONNECTIONS"))
+            .connect(url)
+            .await
+            .expect("failed to connect database");
+
+        Self { pool }
+    }
+
+    pub(super) async fn select_world_by_id(&self, id: i32) -> World {
+        let row = sqlx::query(SELECT_WORLD_BY_ID)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .expect("failed to fetch a world");
+
+        World {
+            id:           row.get(0),
+            randomnumber: row.get(1),
+        }
+    }
+
+    pub(super) async fn select_all_fortunes(&self) -> FortuneRows {
+        let rows = sqlx::query(SELECT_ALL_FORTUNES)
+            .fetch_all(&self.pool)
+            .await
+            .expect("failed to fetch fortunes");
+
+        FortuneRows(rows)
+    }
+
+    pub(super) async fn select_fortune_by_id(&self, id: i32) -> FortuneRows {
+        let rows = sqlx::query(SELECT_FORTUNE_BY_ID)
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await
+            .expect("failed to fetch a fortune");
+
+        FortuneRows(rows)
+    }
+
+    pub(super) async fn update_worlds(&self, ids: &[i32], randomnumbers: &[i32]) {
+        sqlx::query(UPDATE_WORLDS)
+            .bind(ids)
+            .bind(randomnumbers)
+            .execute(&self.pool)
+            .await
+            .expect("failed to update worlds");
+    }
+
+    pub(super) async fn select_api_world(&self, id: i32) -> Option<World> {
+        sqlx::query(SELECT_API_WORLD)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .expect("failed to fetch a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn list_api_worlds(&self, after: i32, limit: i64, offset: i64) -> Vec<World> {
+        sqlx::query(LIST_API_WORLDS)
+            .bind(after)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+            .expect("failed to list worlds")
+            .iter().map(world).collect()
+    }
+
+    pub(super) async fn insert_api_world(&self, id: Option<i32>, randomnumber: i32) -> Option<World> {
+        sqlx::query(INSERT_API_WORLD)
+            .bind(id)
+            .bind(randomnumber)
+            .fetch_optional(&self.pool)
+            .await
+            .expect("failed to insert a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn update_api_world(&self, id: i32, randomnumber: Option<i32>) -> Option<World> {
+        sqlx::query(UPDATE_API_WORLD)
+            .bind(id)
+            .bind(randomnumber)
+            .fetch_optional(&self.pool)
+            .await
+            .expect("failed to update a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn delete_api_world(&self, id: i32) -> bool {
+        sqlx::query(DELETE_API_WORLD)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .expect("failed to delete a world")
+            .rows_affected() > 0
+    }
+}
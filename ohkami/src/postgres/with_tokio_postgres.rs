@@ -0,0 +1,161 @@
+use super::{connector, FortuneRows, SELECT_WORLD_BY_ID, SELECT_ALL_FORTUNES, SELECT_FORTUNE_BY_ID, UPDATE_WORLDS};
+use super::crud::{SELECT_API_WORLD, LIST_API_WORLDS, INSERT_API_WORLD, UPDATE_API_WORLD, DELETE_API_WORLD};
+use crate::models::World;
+use futures_util::stream::StreamExt;
+
+/// One tokio-postgres connection per runtime, pipelining all the queries.
+pub(super) struct Client {
+    client:     tokio_postgres::Client,
+    statements: TechEmpowerStatements,
+    crud:       CrudStatements,
+}
+
+struct TechEmpowerStatements {
+    select_world_by_id:   tokio_postgres::Statement,
+    select_all_fortunes:  tokio_postgres::Statement,
+    select_fortune_by_id: tokio_postgres::Statement,
+    update_worlds:        tokio_postgres::Statement,
+}
+
+/// For `/api/worlds` (see `super::crud`).
+struct CrudStatements {
+    select: tokio_postgres::Statement,
+    list:   tokio_postgres::Statement,
+    insert: tokio_postgres::Statement,
+    update: tokio_postgres::Statement,
+    delete: tokio_postgres::Statement,
+}
+
+fn world(row: &tokio_postgres::Row) -> World {
+    World {
+        id:           row.get(0),
+        randomnumber: row.get(1),
+    }
+}
+
+impl Client {
+    pub(super) async fn connect(url: &str) -> Self {
+        let (client, connection) = tokio_postgres::connect(
+            url,
+            connector()
+        ).await.expect("failed to connect database");
+
+        tokio::spawn(async {
+            if let Err(e) = connection.await {
+                eprintln!("error in database connection: {e}");
+            }
+        });
+        
+        let statements = TechEmpowerStatements {
+            select_world_by_id: client
+                .prepare(SELECT_WORLD_BY_ID)
+                .await
+                .unwrap(),
+            select_all_fortunes: client
+                .prepare(SELECT_ALL_FORTUNES)
+                .await
+                .unwrap(),
+            select_fortune_by_id: client
+                .prepare(SELECT_FORTUNE_BY_ID)
+                .await
+                .unwrap(),
+            update_worlds: client
+                .prepare(UPDATE_WORLDS)
+                .await
+                .unwrap(),
+        };
+
+        let crud = CrudStatements {
+            select: client.prepare(SELECT_API_WORLD).await.unwrap(),
+            list:   client.prepare(LIST_API_WORLDS).await.unwrap(),
+            insert: client.prepare(INSERT_API_WORLD).await.unwrap(),
+            update: client.prepare(UPDATE_API_WORLD).await.unwrap(),
+            delete: client.prepare(DELETE_API_WORLD).await.unwrap(),
+        };
+
+        Self { client, statements, crud }
+    }
+
+    pub(super) async fn select_world_by_id(&self, id: i32) -> World {
+        let row = self.client
+            .query_one(&self.statements.select_world_by_id, &[&id])
+            .await
+            .expect("failed to fetch a world");
+
+        World {
+            id:           row.get(0),
+            randomnumber: row.get(1),
+        }
+    }
+
+    pub(super) async fn select_all_fortunes(&self) -> FortuneRows {
+        let mut rows = std::pin::pin!(self
+            .client
+            .query_raw::<_, _, &[i32; 0]>(&self.statements.select_all_fortunes, &[])
+            .await
+            .expect("failed to fetch fortunes")
+        );
+
+        let mut fortune_rows = Vec::new();
+        while let Some(row) = rows.next().await.transpose().unwrap() {
+            fortune_rows.push(row);
+        }
+
+        FortuneRows(fortune_rows)
+    }
+
+    pub(super) async fn select_fortune_by_id(&self, id: i32) -> FortuneRows {
+        let rows = self.client
+            .query(&self.statements.select_fortune_by_id, &[&id])
+            .await
+            .expect("failed to fetch a fortune");
+
+        FortuneRows(rows)
+    }
+
+    pub(super) async fn update_worlds(&self, ids: &[i32], randomnumbers: &[i32]) {
+        self.client
+            .execute(&self.statements.update_worlds, &[&ids, &randomnumbers])
+            .await
+            .expect("failed to update worlds");
+    }
+
+    pub(super) async fn select_api_world(&self, id: i32) -> Option<World> {
+        self.client
+            .query_opt(&self.crud.select, &[&id])
+            .await
+            .expect("failed to fetch a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn list_api_worlds(&self, after: i32, limit: i64, offset: i64) -> Vec<World> {
+        self.client
+            .query(&self.crud.list, &[&after, &limit, &offset])
+            .await
+            .expect("failed to list worlds")
+            .iter().map(world).collect()
+    }
+
+    pub(super) async fn insert_api_world(&self, id: Option<i32>, randomnumber: i32) -> Option<World> {
+        self.client
+            .query_opt(&self.crud.insert, &[&id, &randomnumber])
+            .await
+            .expect("failed to insert a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn update_api_world(&self, id: i32, randomnumber: Option<i32>) -> Option<World> {
+        self.client
+            .query_opt(&self.crud.update, &[&id, &randomnumber])
+            .await
+            .expect("failed to update a world")
+            .as_ref().map(world)
+    }
+
+    pub(super) async fn delete_api_world(&self, id: i32) -> bool {
+        self.client
+            .execute(&self.crud.delete, &[&id])
+            .await
+            .expect("failed to delete a world") > 0
+    }
+}
@@ -0,0 +1,74 @@
+//! On-demand CPU profiling of the whole process (`pprof` feature),
+//! served at `/debug/pprof/profile?seconds=N&format=(flamegraph|proto)`.
+//!
+//! The sampling is signal-based and process-wide, so all the per-core
+//! runtime threads are profiled, whichever one serves the request.
+
+use ohkami::{Response, serde::Deserialize};
+use ohkami::format::Query;
+
+const DEFAULT_SECONDS: u64 = 10;
+const MAX_SECONDS:     u64 = 300;
+const FREQUENCY:       i32 = 999;
+
+#[derive(Deserialize)]
+pub struct ProfileParams<'req> {
+    seconds: Option<u64>,
+    format:  Option<&'req str>,
+}
+
+enum Format { Flamegraph, Proto }
+
+enum ProfileError {
+    /// another profile is running
+    Busy(String),
+    Report(String),
+}
+
+pub async fn profile(
+    Query(params): Query<ProfileParams<'_>>,
+) -> Response {
+    let seconds = match params.seconds.unwrap_or(DEFAULT_SECONDS) {
+        s @ 1..=MAX_SECONDS => s,
+        _ => return Response::BadRequest().with_text(format!("`seconds` must be in 1..={MAX_SECONDS}")),
+    };
+    let format = match params.format {
+        None | Some("flamegraph") => Format::Flamegraph,
+        Some("proto")             => Format::Proto,
+        Some(_) => return Response::BadRequest().with_text("`format` must be `flamegraph` or `proto`"),
+    };
+
+    /* the profiler isn't `Send`; let a blocking thread hold it while sleeping */
+    let profiled = tokio::task::spawn_blocking(move || {
+        let report_error = |e: pprof::Error| ProfileError::Report(e.to_string());
+
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(|e| ProfileError::Busy(e.to_string()))?;
+
+        std::thread::sleep(std::time::Duration::from_secs(seconds));
+
+        let report = guard.report().build().map_err(report_error)?;
+        let mut body = Vec::new();
+        match format {
+            Format::Flamegraph => {
+                report.flamegraph(&mut body).map_err(report_error)?;
+                Ok(("image/svg+xml", body))
+            }
+            Format::Proto => {
+                use pprof::protos::Message;
+                report.pprof().map_err(report_error)?
+                    .write_to_vec(&mut body).map_err(|e| ProfileError::Report(e.to_string()))?;
+                Ok(("application/octet-stream", body))
+            }
+        }
+    }).await.expect("profiling task panicked");
+
+    match profiled {
+        Ok((content_type, body))     => Response::OK().with_payload(content_type, body),
+        Err(ProfileError::Busy(e))   => Response::Conflict().with_text(e),
+        Err(ProfileError::Report(e)) => Response::InternalServerError().with_text(e),
+    }
+}
@@ -0,0 +1,23 @@
+//! `/sse?events=N&interval_ms=M` (`sse` feature), streaming N random
+//! `World` rows as JSON events, one every M milliseconds.
+
+use crate::{models::SseMeta, postgres::Postgres};
+use ohkami::{prelude::*, format::Query, sse::DataStream};
+
+pub async fn sse(
+    Query(q): Query<SseMeta<'_>>,
+    Context(db): Context<'_, Postgres>,
+) -> DataStream {
+    let (events, interval) = q.parse();
+    let db = db.clone();
+
+    DataStream::new(move |mut s| async move {
+        for i in 0..events {
+            if i > 0 && !interval.is_zero() {
+                tokio::time::sleep(interval).await;
+            }
+            let world = db.select_random_world().await;
+            s.send(serde_json::to_string(&world).unwrap());
+        }
+    })
+}
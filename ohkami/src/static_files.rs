@@ -0,0 +1,108 @@
+//! `GET /static/:name` serves the files of `STATIC_DIR` (`../static` by
+//! default, generated by `gen-static.sh`) with `Content-Length`, a single
+//! `Range` (206, or 416 if unsatisfiable), and `ETag`/`Last-Modified`
+//! validation by `If-None-Match`/`If-Modified-Since` (304).
+//!
+//! Only one path segment is served, as ohkami's router has no catch-all
+//! parameter. The (ranged) content is read into memory per request, as
+//! ohkami's responses have no streaming file body, so no `sendfile` either.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use ohkami::{Request, Response};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+static STATIC_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    std::env::var("STATIC_DIR").unwrap_or_else(|_| String::from("../static")).into()
+});
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css")  => "text/css",
+        Some("js")   => "text/javascript",
+        Some("json") => "application/json",
+        Some("txt")  => "text/plain; charset=utf-8",
+        _            => "application/octet-stream",
+    }
+}
+
+/// `None` for anything but a single `bytes=` range, which is ignored and
+/// the whole file is served. `Some(None)` if the range is unsatisfiable.
+fn byte_range(range: &str, len: u64) -> Option<Option<(u64, u64)>> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    if end.contains(',') {
+        return None
+    }
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "")     => return None,
+        ("", suffix) => (len.saturating_sub(suffix.parse().ok()?), len.saturating_sub(1)),
+        (start, "")  => (start.parse().ok()?, len.saturating_sub(1)),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(len.saturating_sub(1))),
+    };
+    Some((start <= end && start < len).then_some((start, end)))
+}
+
+pub async fn static_file(name: &str, req: &Request) -> Response {
+    let path = Path::new(name);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Response::NotFound()
+    }
+
+    let Ok(mut file) = tokio::fs::File::open(STATIC_DIR.join(path)).await else {
+        return Response::NotFound()
+    };
+    let metadata = file.metadata().await.expect("failed to read file metadata");
+    if !metadata.is_file() {
+        return Response::NotFound()
+    }
+
+    let len = metadata.len();
+    /* in whole seconds, as `Last-Modified` */
+    let mtime_secs = metadata.modified().ok()
+        .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    let mtime = UNIX_EPOCH + Duration::from_secs(mtime_secs);
+
+    let etag          = format!(r#""{mtime_secs:x}-{len:x}""#);
+    let last_modified = httpdate::fmt_http_date(mtime);
+
+    let not_modified = match req.headers.IfNoneMatch() {
+        Some(tags) => tags.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag),
+        None       => req.headers.IfModifiedSince()
+            .and_then(|since| httpdate::parse_http_date(since).ok())
+            .is_some_and(|since: SystemTime| mtime <= since),
+    };
+    if not_modified {
+        let mut res = Response::NotModified();
+        res.headers.set().ETag(etag).LastModified(last_modified);
+        return res
+    }
+
+    let range = req.headers.Range().and_then(|range| byte_range(range, len));
+    let (mut res, start, end) = match range {
+        None               => (Response::OK(), 0, len.saturating_sub(1)),
+        Some(Some((s, e))) => (Response::PartialContent(), s, e),
+        Some(None)         => {
+            let mut res = Response::RangeNotSatisfiable();
+            res.headers.set().ContentRange(format!("bytes */{len}"));
+            return res
+        }
+    };
+
+    let mut content = vec![0; if len == 0 { 0 } else { (end - start + 1) as usize }];
+    file.seek(std::io::SeekFrom::Start(start)).await.expect("failed to seek file");
+    file.read_exact(&mut content).await.expect("failed to read file");
+
+    res.headers.set()
+        .AcceptRanges("bytes")
+        .ETag(etag)
+        .LastModified(last_modified);
+    if range.is_some() {
+        res.headers.set().ContentRange(format!("bytes {start}-{end}/{len}"));
+    }
+    res.with_payload(content_type(name), content)
+}
@@ -1,24 +1,173 @@
+//! `/fortunes` rendering. The engine is selected by cargo features
+//! (`askama`, `sailfish`, `handwritten`), falling back to yarte.
+//! Template sources are shared with axum: `../templates` (see `yarte.toml`,
+//! `askama.toml` and `sailfish.toml`).
+
 use ohkami::{IntoResponse, Response};
-use yarte::Template;
 use crate::models::Fortune;
 
-#[derive(Template)]
-#[template(src = r#"
-<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table><tr><th>id</th><th>message</th></tr>
-    {{~# each fortunes ~}}
-    <tr><td>{{id}}</td><td>{{message}}</td></tr>
-    {{~/each ~}}
-</table></body></html>
-"#)]
-pub struct FortunesTemplate {
-    pub fortunes: Vec<Fortune>,
+#[cfg(any(
+    all(feature = "askama", feature = "sailfish"),
+    all(feature = "askama", feature = "handwritten"),
+    all(feature = "sailfish", feature = "handwritten"),
+))]
+compile_error!("select at most one template engine of `askama`, `sailfish` and `handwritten`");
+
+#[cfg(not(any(feature = "askama", feature = "sailfish", feature = "handwritten")))]
+#[derive(yarte::Template)]
+#[template(path = "fortunes.html.hbs")]
+pub struct FortunesTemplate<'r> {
+    pub fortunes: Vec<Fortune<'r>>,
+}
+#[cfg(not(any(feature = "askama", feature = "sailfish", feature = "handwritten")))]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "yarte";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        yarte::Template::call(&self)
+    }
+}
+
+#[cfg(feature = "askama")]
+#[derive(askama::Template)]
+#[template(path = "fortunes.html")]
+pub struct FortunesTemplate<'r> {
+    pub fortunes: Vec<Fortune<'r>>,
+}
+#[cfg(feature = "askama")]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "askama";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        askama::Template::render(&self).map_err(|_| std::fmt::Error)
+    }
+}
+
+#[cfg(feature = "sailfish")]
+#[derive(sailfish::TemplateOnce)]
+#[template(path = "fortunes.stpl")]
+pub struct FortunesTemplate<'r> {
+    pub fortunes: Vec<Fortune<'r>>,
 }
+#[cfg(feature = "sailfish")]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "sailfish";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        sailfish::TemplateOnce::render_once(self).map_err(|_| std::fmt::Error)
+    }
+}
+
+#[cfg(feature = "handwritten")]
+pub struct FortunesTemplate<'r> {
+    pub fortunes: Vec<Fortune<'r>>,
+}
+#[cfg(feature = "handwritten")]
+impl FortunesTemplate<'_> {
+    pub const ENGINE: &'static str = "handwritten";
+
+    pub fn render(self) -> Result<String, std::fmt::Error> {
+        const HEAD: &str = "<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table><tr><th>id</th><th>message</th></tr>";
+        const TAIL: &str = "</table></body></html>";
 
-impl IntoResponse for FortunesTemplate {
+        let mut html = String::with_capacity(HEAD.len() + TAIL.len() + self.fortunes.len() * 64);
+        html.push_str(HEAD);
+        for Fortune { id, message } in &self.fortunes {
+            html.push_str("<tr><td>");
+            html.push_str(itoa::Buffer::new().format(*id));
+            html.push_str("</td><td>");
+            escape_html(message, &mut html);
+            html.push_str("</td></tr>");
+        }
+        html.push_str(TAIL);
+
+        Ok(html)
+    }
+}
+
+/// Escapes the same characters as yarte (`<>&"'/`), so that this engine's page
+/// is byte-identical to yarte's. askama and sailfish don't escape `/`, and
+/// sailfish escapes `'` as `&#039;`: their pages differ (see `bench_render`).
+#[cfg(feature = "handwritten")]
+#[inline]
+fn escape_html(s: &str, buf: &mut String) {
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let escaped = match b {
+            b'<'  => "&lt;",
+            b'>'  => "&gt;",
+            b'&'  => "&amp;",
+            b'"'  => "&quot;",
+            b'\'' => "&#x27;",
+            b'/'  => "&#x2f;",
+            _ => continue,
+        };
+        buf.push_str(&s[start..i]);
+        buf.push_str(escaped);
+        start = i + 1;
+    }
+    buf.push_str(&s[start..]);
+}
+
+/// yarte's page, which `bench_render` compares the selected engine's to.
+#[derive(yarte::Template)]
+#[template(path = "fortunes.html.hbs")]
+struct YartePage<'r> {
+    fortunes: Vec<Fortune<'r>>,
+}
+
+impl IntoResponse for FortunesTemplate<'_> {
     fn into_response(self) -> Response {
-        match Template::call(&self) {
+        match self.render() {
             Ok(template) => Response::OK().with_html(template),
             Err(_)       => Response::InternalServerError(),
         }
     }
 }
+
+/// Renders the `postgres/init.sql` fortunes `iterations` times, as `/fortunes`
+/// does (request-time fortune and sort included) but without any HTTP or
+/// database involved, and prints the average cost per render, the page's
+/// size and whether it's byte-identical to yarte's.
+pub fn bench_render(iterations: u32) {
+    let messages = include_str!("../../postgres/init.sql")
+        .lines()
+        .filter_map(|line| line.strip_prefix("INSERT INTO Fortune (id, message) VALUES ("))
+        .map(|values| {
+            let (id, message) = values.split_once(", '").unwrap();
+            let message = message.strip_suffix("');").unwrap().replace("''", "'");
+            (id.parse::<i32>().unwrap(), message)
+        })
+        .collect::<Vec<_>>();
+
+    let fortunes = || {
+        let mut fortunes = messages.iter()
+            .map(|(id, message)| Fortune { id: *id, message })
+            .collect::<Vec<_>>();
+        fortunes.push(Fortune {
+            id:      0,
+            message: "Additional fortune added at request time.",
+        });
+        fortunes.sort_unstable_by(|a, b| str::cmp(a.message, b.message));
+        fortunes
+    };
+
+    let html  = FortunesTemplate { fortunes: fortunes() }.render().unwrap();
+    let yarte = yarte::Template::call(&YartePage { fortunes: fortunes() }).unwrap();
+
+    let start = std::time::Instant::now();
+    for _ in 0..iterations {
+        let html = FortunesTemplate { fortunes: fortunes() }.render().unwrap();
+        std::hint::black_box(html);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{{\"engine\": \"{}\", \"iterations\": {iterations}, \"ns_per_render\": {:.1}, \"bytes_per_render\": {}, \"yarte_bytes\": {}, \"same_as_yarte\": {}}}",
+        FortunesTemplate::ENGINE,
+        elapsed.as_nanos() as f64 / iterations as f64,
+        html.len(),
+        yarte.len(),
+        html == yarte,
+    );
+}
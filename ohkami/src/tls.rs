@@ -0,0 +1,73 @@
+//! rustls server configuration for the `tls` feature.
+//!
+//! The certificate chain and private key are loaded from the PEM files at
+//! `TLS_CERT` and `TLS_KEY`, or a self-signed certificate for `localhost`
+//! is generated at startup if they are not set. The config is built once and
+//! shared by all runtimes, so that session resumption (by both the stateful
+//! cache and tickets) works across the `SO_REUSEPORT` listeners.
+
+use std::sync::{Arc, LazyLock};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+    server::ServerSessionMemoryCache,
+    ServerConfig,
+};
+
+/// Number of sessions kept for stateful resumption
+/// (`TLS_SESSION_CACHE_SIZE`, 4096 by default).
+fn session_cache_size() -> usize {
+    std::env::var("TLS_SESSION_CACHE_SIZE").map(|n| n.parse().expect("invalid TLS_SESSION_CACHE_SIZE")).unwrap_or(4096)
+}
+
+fn load_pem(cert_path: &str, key_path: &str) -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+    let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(
+        std::fs::File::open(cert_path).expect("failed to open TLS_CERT")
+    )).collect::<Result<Vec<_>, _>>().expect("invalid certificate in TLS_CERT");
+
+    let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(
+        std::fs::File::open(key_path).expect("failed to open TLS_KEY")
+    )).expect("invalid private key in TLS_KEY").expect("no private key in TLS_KEY");
+
+    (certs, key)
+}
+
+fn self_signed() -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+    println!("TLS_CERT and TLS_KEY are not set, generating a self-signed certificate for localhost");
+
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(
+        vec!["localhost".into(), "127.0.0.1".into()]
+    ).expect("failed to generate a self-signed certificate");
+
+    (
+        vec![cert.der().clone()],
+        PrivatePkcs8KeyDer::from(key_pair.serialize_der()).into(),
+    )
+}
+
+static CONFIG: LazyLock<ServerConfig> = LazyLock::new(|| {
+    let (certs, key) = match (std::env::var("TLS_CERT"), std::env::var("TLS_KEY")) {
+        (Ok(cert_path), Ok(key_path)) => load_pem(&cert_path, &key_path),
+        (Err(_), Err(_)) => self_signed(),
+        _ => panic!("set both or neither of TLS_CERT and TLS_KEY"),
+    };
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("unsupported TLS protocol versions")
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .expect("invalid certificate or private key");
+
+    /* ohkami speaks HTTP/1.1 only */
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    config.session_storage = ServerSessionMemoryCache::new(session_cache_size());
+    config.ticketer = rustls::crypto::ring::Ticketer::new().expect("failed to build the session ticketer");
+
+    config
+});
+
+/// The shared server config. Cloning it shares the session cache and the ticketer.
+pub fn config() -> ServerConfig {
+    CONFIG.clone()
+}
@@ -0,0 +1,62 @@
+//! WebSocket endpoints (`ws` feature):
+//!
+//! * `/ws/echo` sends every text or binary message back to its sender.
+//! * `/ws/broadcast` relays every text message to all the connected sockets,
+//!   including the sender, across all the runtimes.
+
+use ohkami::ws::{WebSocketContext, WebSocket, Message};
+use std::sync::LazyLock;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Messages kept for slow subscribers (`WS_BROADCAST_CAPACITY`, 1024 by default).
+/// A subscriber lagging behind more than this skips the oldest ones.
+static BROADCAST: LazyLock<broadcast::Sender<String>> = LazyLock::new(|| {
+    let capacity = std::env::var("WS_BROADCAST_CAPACITY").map(|n| n.parse().expect("invalid WS_BROADCAST_CAPACITY")).unwrap_or(1024);
+    broadcast::channel(capacity).0
+});
+
+pub async fn echo(ctx: WebSocketContext<'_>) -> WebSocket {
+    ctx.upgrade(|mut conn| async move {
+        loop {
+            let sent = match conn.recv().await {
+                Ok(Some(Message::Text(text)))   => conn.send(text).await,
+                Ok(Some(Message::Binary(data))) => conn.send(data).await,
+                Ok(Some(Message::Close(_))) | Ok(None) | Err(_) => break,
+                Ok(Some(_)) => Ok(()),
+            };
+            if sent.is_err() {break}
+        }
+    })
+}
+
+pub async fn broadcast(ctx: WebSocketContext<'_>) -> WebSocket {
+    ctx.upgrade(|conn| async move {
+        let mut subscription = BROADCAST.subscribe();
+        let (mut r, mut w) = conn.split();
+
+        let publish = async {
+            loop {
+                match r.recv().await {
+                    Ok(Some(Message::Text(text))) => {let _ = BROADCAST.send(text);}
+                    Ok(Some(Message::Close(_))) | Ok(None) | Err(_) => break,
+                    Ok(Some(_)) => (),
+                }
+            }
+        };
+        let relay = async {
+            loop {
+                match subscription.recv().await {
+                    Ok(text) => if w.send(text).await.is_err() {break},
+                    Err(RecvError::Lagged(_)) => (),
+                    Err(RecvError::Closed)    => break,
+                }
+            }
+        };
+
+        /* whichever ends first closes the connection */
+        tokio::select! {
+            _ = publish => (),
+            _ = relay   => (),
+        }
+    })
+}
@@ -64,4 +64,14 @@ INSERT INTO "Fortune" (id, message) VALUES (10, 'Computers make very fast, very
 INSERT INTO "Fortune" (id, message) VALUES (11, '<script>alert("This should not be displayed in a browser alert box.");</script>');
 INSERT INTO "Fortune" (id, message) VALUES (12, 'フレームワークのベンチマーク');
 
+-- `/api/worlds` (CRUD) apart from `World`, so that its writes and deletes
+-- don't affect the TechEmpower endpoints. Inserted ids continue from 10001.
+CREATE TABLE ApiWorld (
+  id integer GENERATED BY DEFAULT AS IDENTITY (START WITH 10001) PRIMARY KEY,
+  randomNumber integer NOT NULL
+);
+GRANT ALL PRIVILEGES ON ApiWorld to benchmarkdbuser;
+
+INSERT INTO ApiWorld (id, randomnumber) SELECT id, randomnumber FROM World;
+
 COMMIT;
@@ -0,0 +1,65 @@
+-- Mixed read/write workload on `/api/worlds?writes=N`, where N% (20 by
+-- default) of the requests are writes split evenly between PUT, PATCH, POST
+-- and DELETE, and the rest are reads: 7 `GET /api/worlds/:id` per page of
+-- `GET /api/worlds` (keyset paginated from a random id).
+--
+-- Each thread DELETEs the worlds it has POSTed (by their `Location`), so
+-- the table keeps its size. A DELETE with none to delete is a GET instead.
+
+local writes = 20
+local created = {}
+
+local json = { ["Content-Type"] = "application/json" }
+
+local threads = 0
+
+-- numbers the threads, so that they don't all send the same requests in
+-- the same order
+function setup(thread)
+    threads = threads + 1
+    thread:set("id", threads)
+end
+
+function init(args)
+    math.randomseed(os.time() + id)
+    writes = tonumber(string.match(wrk.path, "writes=(%d+)") or "20")
+end
+
+local function random_id()
+    return math.random(1, 10000)
+end
+
+local function read()
+    if math.random(1, 8) == 8 then
+        return wrk.format("GET", "/api/worlds?after=" .. random_id() .. "&limit=20")
+    end
+    return wrk.format("GET", "/api/worlds/" .. random_id())
+end
+
+function request()
+    if math.random(1, 100) > writes then
+        return read()
+    end
+
+    local kind = math.random(1, 4)
+    local randomnumber = random_id()
+    if kind == 1 then
+        return wrk.format("PUT", "/api/worlds/" .. random_id(), json,
+            string.format('{"randomNumber":%d}', randomnumber))
+    elseif kind == 2 then
+        return wrk.format("PATCH", "/api/worlds/" .. random_id(), json,
+            string.format('{"randomNumber":%d}', randomnumber))
+    elseif kind == 3 then
+        return wrk.format("POST", "/api/worlds", json,
+            string.format('{"randomNumber":%d}', randomnumber))
+    elseif #created > 0 then
+        return wrk.format("DELETE", table.remove(created))
+    end
+    return read()
+end
+
+function response(status, headers, body)
+    if status == 201 then
+        created[#created + 1] = headers["Location"] or headers["location"]
+    end
+end
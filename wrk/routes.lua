@@ -0,0 +1,42 @@
+-- GETs all the routes of the `many-routes` feature (`ROUTES`, 1000 by default)
+-- in a random order, with the shapes of `ohkami/build.rs`. The parameterized
+-- ones get random ids. The paths are generated up front and cycled through.
+
+local paths = {}
+local counter = 0
+
+local threads = 0
+
+-- numbers the threads, for each to shuffle the routes in its own order
+function setup(thread)
+    threads = threads + 1
+    thread:set("id", threads)
+end
+
+function init(args)
+    math.randomseed(os.time() + id)
+    local routes = tonumber(os.getenv("ROUTES") or "1000")
+
+    for i = 0, routes - 1 do
+        local g, l = math.floor(i / 24), math.floor(i % 24 / 3)
+        local shape = i % 3
+        if shape == 0 then
+            paths[#paths + 1] = string.format("/r/g%d/s%d", g, l)
+        elseif shape == 1 then
+            paths[#paths + 1] = string.format("/r/g%d/s%d/%d", g, l, math.random(1, 10000))
+        else
+            paths[#paths + 1] = string.format("/r/g%d/s%d/%d/items", g, l, math.random(1, 10000))
+        end
+    end
+
+    -- Fisher-Yates, so that consecutive requests don't share prefixes
+    for i = #paths, 2, -1 do
+        local j = math.random(1, i)
+        paths[i], paths[j] = paths[j], paths[i]
+    end
+end
+
+function request()
+    counter = counter % #paths + 1
+    return wrk.format(nil, paths[counter])
+end
@@ -0,0 +1,38 @@
+-- POST bodies for `/worlds/batch?size=N` (20 by default): JSON arrays of N
+-- worlds with distinct random ids. A pool of them is generated up front and
+-- cycled through, so that generating them isn't part of the measurement.
+
+-- Each thread's Lua state starts with the same `math.random` sequence, so
+-- `setup` numbers the threads and `init` seeds it with that number.
+local threads = 0
+
+function setup(thread)
+    threads = threads + 1
+    thread:set("id", threads)
+end
+
+local bodies = {}
+local counter = 0
+
+function init(args)
+    math.randomseed(os.time() + id)
+    local size = tonumber(string.match(wrk.path, "size=(%d+)") or "20")
+
+    wrk.headers["Content-Type"] = "application/json"
+    for b = 1, 100 do
+        local taken, worlds = {}, {}
+        while #worlds < size do
+            local id = math.random(1, 10000)
+            if not taken[id] then
+                taken[id] = true
+                worlds[#worlds + 1] = string.format('{"id":%d,"randomNumber":%d}', id, math.random(1, 10000))
+            end
+        end
+        bodies[b] = "[" .. table.concat(worlds, ",") .. "]"
+    end
+end
+
+function request()
+    counter = counter % #bodies + 1
+    return wrk.format("POST", nil, nil, bodies[counter])
+end